@@ -0,0 +1,10 @@
+macro_rules! cfg_tracing {
+	($($item:item)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            $(
+                $item
+            )*
+        }
+    }
+}
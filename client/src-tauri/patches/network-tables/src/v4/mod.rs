@@ -0,0 +1,18 @@
+#[cfg(feature = "client-v4")]
+pub mod client;
+#[cfg(feature = "client-v4")]
+pub mod client_config;
+pub mod message_type;
+pub mod messages;
+pub mod subscription;
+pub mod topic;
+
+pub use message_type::*;
+pub use messages::*;
+pub use subscription::*;
+pub use topic::*;
+
+#[cfg(feature = "client-v4")]
+pub use client::Client;
+#[cfg(feature = "client-v4")]
+pub use client_config::Config;
@@ -1,22 +1,26 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::net::Ipv4Addr;
-
 use tauri::Manager;
 mod telemetry;
 use tracing_subscriber::FmtSubscriber;
 mod close_splashscreen;
 use close_splashscreen::close_splashscreen;
+use telemetry::commands::{
+    add_subscription_set, get_impact_log, get_match_clock, get_match_context, get_power_stats,
+    get_replay_status, get_telemetry_snapshot, get_telemetry_status, get_telemetry_target,
+    get_topic, list_choosers, list_subscription_sets, list_topics, pause_replay, pause_telemetry,
+    play_replay, publish_value, reconnect_telemetry, remove_subscription_set, seek_replay,
+    select_chooser_option, set_replay_speed, set_telemetry_target, set_watchdog_config,
+    start_replay,
+};
+use telemetry::TelemetrySupervisor;
 
 #[derive(Clone, serde::Serialize)]
 struct Payload {
     message: String,
 }
 
-const NTABLE_IP: Ipv4Addr = Ipv4Addr::new(10, 12, 80, 2);
-const NTABLE_PORT: u16 = 5810;
-
 fn main() {
     let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
 
@@ -26,19 +30,49 @@ fn main() {
         .finish();
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
+    // share our runtime with tauri so commands and telemetry run on the same one
+    tauri::async_runtime::set(rt.handle().clone());
+
     rt.block_on(async {
         tauri::Builder::default()
             .setup(|app| {
                 // create app handle and send it to our event listeners
                 let app_handle = app.app_handle();
+                let config = telemetry::load_config(&app_handle);
 
-                tokio::spawn(async move {
-                    crate::telemetry::subscribe_topics(app_handle, NTABLE_IP, NTABLE_PORT).await;
-                });
+                app.manage(TelemetrySupervisor::new(app_handle, config));
+                app.state::<TelemetrySupervisor>().restart();
 
                 Ok(())
             })
-            .invoke_handler(tauri::generate_handler![close_splashscreen])
+            .invoke_handler(tauri::generate_handler![
+                close_splashscreen,
+                get_telemetry_status,
+                get_telemetry_target,
+                set_telemetry_target,
+                reconnect_telemetry,
+                pause_telemetry,
+                set_watchdog_config,
+                publish_value,
+                list_choosers,
+                select_chooser_option,
+                get_match_context,
+                list_subscription_sets,
+                add_subscription_set,
+                remove_subscription_set,
+                list_topics,
+                get_topic,
+                get_telemetry_snapshot,
+                get_impact_log,
+                get_power_stats,
+                get_match_clock,
+                start_replay,
+                play_replay,
+                pause_replay,
+                seek_replay,
+                set_replay_speed,
+                get_replay_status
+            ])
             .run(tauri::generate_context!())
             .expect("failed to run app")
     })
@@ -0,0 +1,218 @@
+use std::time::{Duration, Instant};
+
+use network_tables::Value;
+use serde::{Deserialize, Serialize};
+
+/// Thresholds used by the [`BatteryMonitor`], in volts unless noted.
+///
+/// The robot counts as loaded while its speed is above `load_speed` (m/s) or
+/// its acceleration is above `load_accel` (g). Once it has been below both for
+/// `settle_ms`, the voltage is taken as the resting voltage. Levels are only
+/// decided from the resting voltage, so sag under load doesn't raise alerts,
+/// and a level is only left once the voltage is `hysteresis` past its
+/// threshold. Any dip below `brownout` is counted, loaded or not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    pub low: f64,
+    pub critical: f64,
+    pub hysteresis: f64,
+    pub brownout: f64,
+    pub load_speed: f64,
+    pub load_accel: f64,
+    pub settle_ms: u64,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            low: 12.0,
+            critical: 11.5,
+            hysteresis: 0.2,
+            brownout: 6.8,
+            load_speed: 0.25,
+            load_accel: 0.1,
+            settle_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryLevel {
+    Good,
+    Low,
+    Critical,
+}
+
+/// Why a `battery_state` event was emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryChange {
+    /// The level moved to a different band.
+    Level,
+    /// The voltage dipped into the brownout range.
+    Brownout,
+}
+
+/// The payload of the `battery_state` event.
+///
+/// `sag` is how far the voltage is below the resting voltage, and is only
+/// known while the robot is under load.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatteryState {
+    pub change: BatteryChange,
+    pub level: Option<BatteryLevel>,
+    pub voltage: f64,
+    pub resting_voltage: Option<f64>,
+    pub sag: Option<f64>,
+    pub under_load: bool,
+    pub brownouts: u32,
+}
+
+/// Watches the `voltage` topic and tells resting voltage apart from sag
+/// under load, using the chassis speed and acceleration as a proxy for load.
+pub struct BatteryMonitor {
+    config: BatteryConfig,
+    speed: (f64, f64),
+    accel: (f64, f64),
+    resting_since: Option<Instant>,
+    voltage: f64,
+    resting_voltage: Option<f64>,
+    level: Option<BatteryLevel>,
+    in_brownout: bool,
+    brownouts: u32,
+}
+
+impl BatteryMonitor {
+    pub fn new(config: BatteryConfig, now: Instant) -> Self {
+        Self {
+            config,
+            speed: (0.0, 0.0),
+            accel: (0.0, 0.0),
+            resting_since: Some(now),
+            voltage: 0.0,
+            resting_voltage: None,
+            level: None,
+            in_brownout: false,
+            brownouts: 0,
+        }
+    }
+
+    /// Applies an update to the topic `topic_name`, which is the display name
+    /// the frontend knows it by.
+    ///
+    /// Returns the state to emit if the level changed or a brownout started.
+    pub fn update(
+        &mut self,
+        topic_name: &str,
+        value: &Value,
+        now: Instant,
+    ) -> Option<BatteryState> {
+        let number = value.as_f64()?;
+
+        match topic_name {
+            "chassis-x-speed" => self.speed.0 = number,
+            "chassis-y-speed" => self.speed.1 = number,
+            "accx" => self.accel.0 = number,
+            "accy" => self.accel.1 = number,
+            "voltage" => return self.update_voltage(number, now),
+            _ => return None,
+        }
+
+        if self.under_load() {
+            self.resting_since = None;
+        } else if self.resting_since.is_none() {
+            self.resting_since = Some(now);
+        }
+        None
+    }
+
+    /// Returns whether the voltage is in the brownout range, from the moment
+    /// it dips below `brownout` until it is back above it by the hysteresis.
+    pub fn in_brownout(&self) -> bool {
+        self.in_brownout
+    }
+
+    fn under_load(&self) -> bool {
+        self.speed.0.hypot(self.speed.1) > self.config.load_speed
+            || self.accel.0.hypot(self.accel.1) > self.config.load_accel
+    }
+
+    fn update_voltage(&mut self, voltage: f64, now: Instant) -> Option<BatteryState> {
+        // the robot publishes -999 and 0 before it has a reading
+        if voltage <= 0.0 {
+            return None;
+        }
+        self.voltage = voltage;
+
+        if voltage < self.config.brownout {
+            if !self.in_brownout {
+                self.in_brownout = true;
+                self.brownouts += 1;
+                tracing::warn!("Brownout-range dip to {:.2} V", voltage);
+                return Some(self.state(BatteryChange::Brownout));
+            }
+        } else if voltage > self.config.brownout + self.config.hysteresis {
+            self.in_brownout = false;
+        }
+
+        let settled = self.resting_since.map_or(false, |since| {
+            now.duration_since(since) >= Duration::from_millis(self.config.settle_ms)
+        });
+        if !settled {
+            return None;
+        }
+
+        self.resting_voltage = Some(voltage);
+        let level = self.next_level(voltage);
+        if self.level == Some(level) {
+            return None;
+        }
+
+        self.level = Some(level);
+        Some(self.state(BatteryChange::Level))
+    }
+
+    /// Decides the level of a resting voltage, only leaving the current level
+    /// once the voltage is past its threshold by the hysteresis.
+    fn next_level(&self, voltage: f64) -> BatteryLevel {
+        let BatteryConfig {
+            low,
+            critical,
+            hysteresis,
+            ..
+        } = self.config;
+
+        match self.level {
+            Some(BatteryLevel::Critical) if voltage <= critical + hysteresis => {
+                BatteryLevel::Critical
+            }
+            Some(BatteryLevel::Critical) | Some(BatteryLevel::Low)
+                if voltage <= low + hysteresis && voltage >= critical =>
+            {
+                BatteryLevel::Low
+            }
+            _ if voltage < critical => BatteryLevel::Critical,
+            _ if voltage < low => BatteryLevel::Low,
+            _ => BatteryLevel::Good,
+        }
+    }
+
+    fn state(&self, change: BatteryChange) -> BatteryState {
+        let under_load = self.resting_since.is_none();
+
+        BatteryState {
+            change,
+            level: self.level,
+            voltage: self.voltage,
+            resting_voltage: self.resting_voltage,
+            sag: self
+                .resting_voltage
+                .filter(|_| under_load)
+                .map(|resting| resting - self.voltage),
+            under_load,
+            brownouts: self.brownouts,
+        }
+    }
+}
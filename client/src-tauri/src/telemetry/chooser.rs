@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use network_tables::Value;
+use serde::Serialize;
+
+/// The value of `.type` that WPILib's `SendableChooser` publishes.
+const CHOOSER_TYPE: &str = "String Chooser";
+
+/// A `SendableChooser` published by the robot, e.g. the autonomous selector.
+///
+/// `path` is the full NetworkTables path of the chooser, like
+/// `/SmartDashboard/Auto Chooser`, and `name` is the same path without the
+/// `/SmartDashboard/` prefix. `pending` is an option the dashboard selected
+/// that the robot has not confirmed through `active` yet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Chooser {
+    pub path: String,
+    pub name: String,
+    pub options: Vec<String>,
+    pub default: Option<String>,
+    pub active: Option<String>,
+    pub selected: Option<String>,
+    pub pending: Option<String>,
+    #[serde(skip)]
+    is_chooser: bool,
+}
+
+/// Payload of the `chooser_confirmed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChooserConfirmed {
+    pub path: String,
+    pub option: String,
+}
+
+/// A change to a chooser caused by a telemetry update.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChooserEvent {
+    /// Any field of the chooser changed.
+    Updated(Chooser),
+    /// The robot echoed a pending selection back through `active`.
+    Confirmed(ChooserConfirmed),
+}
+
+/// Recognises `SendableChooser` groups among the raw topics and keeps them
+/// as structured [`Chooser`]s.
+///
+/// A chooser is published as several sibling topics (`.type`, `options`,
+/// `default`, `active` and `selected`). A group is treated as a chooser once
+/// its `.type` is `String Chooser` or it publishes a list of `options`.
+#[derive(Default)]
+pub struct ChooserRegistry {
+    groups: HashMap<String, Chooser>,
+}
+
+impl ChooserRegistry {
+    /// Applies an update to the full topic name `topic_name`, returning what
+    /// changed about the chooser it belongs to, if any.
+    pub fn update(&mut self, topic_name: &str, value: &Value) -> Vec<ChooserEvent> {
+        let (path, key) = match topic_name.rsplit_once('/') {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+
+        if !matches!(key, ".type" | "options" | "default" | "active" | "selected") {
+            return Vec::new();
+        }
+
+        let chooser = self
+            .groups
+            .entry(path.to_string())
+            .or_insert_with(|| Chooser {
+                path: path.to_string(),
+                name: path.trim_start_matches("/SmartDashboard/").to_string(),
+                ..Default::default()
+            });
+        let before = chooser.clone();
+        let mut events = Vec::new();
+
+        match key {
+            ".type" => chooser.is_chooser |= value.as_str() == Some(CHOOSER_TYPE),
+            "options" => {
+                if let Some(options) = value.as_array() {
+                    chooser.options = options
+                        .iter()
+                        .filter_map(|option| option.as_str().map(str::to_string))
+                        .collect();
+                    chooser.is_chooser = true;
+                }
+            }
+            "default" => chooser.default = value.as_str().map(str::to_string),
+            "selected" => chooser.selected = value.as_str().map(str::to_string),
+            "active" => {
+                chooser.active = value.as_str().map(str::to_string);
+
+                if chooser.pending.is_some() && chooser.pending == chooser.active {
+                    let option = chooser.pending.take().unwrap();
+                    events.push(ChooserEvent::Confirmed(ChooserConfirmed {
+                        path: chooser.path.clone(),
+                        option,
+                    }));
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        if chooser.is_chooser && *chooser != before {
+            events.insert(0, ChooserEvent::Updated(chooser.clone()));
+        }
+
+        events
+    }
+
+    /// Returns every chooser seen so far.
+    pub fn list(&self) -> Vec<Chooser> {
+        let mut choosers: Vec<Chooser> = self
+            .groups
+            .values()
+            .filter(|chooser| chooser.is_chooser)
+            .cloned()
+            .collect();
+        choosers.sort_by(|a, b| a.path.cmp(&b.path));
+        choosers
+    }
+
+    /// Checks that `option` can be selected on the chooser at `path`.
+    ///
+    /// Returns the topic the selection has to be published to.
+    pub fn selection_topic(&self, path: &str, option: &str) -> Result<String, String> {
+        let chooser = self
+            .groups
+            .get(path)
+            .filter(|chooser| chooser.is_chooser)
+            .ok_or_else(|| format!("No chooser at {}", path))?;
+
+        if !chooser.options.iter().any(|o| o == option) {
+            return Err(format!("{} is not an option of {}", option, chooser.name));
+        }
+
+        Ok(format!("{}/selected", path))
+    }
+
+    /// Marks `option` as selected on the chooser at `path`, pending
+    /// confirmation from the robot, once the selection has been published.
+    ///
+    /// Returns whether the robot already has `option` active, in which case
+    /// there is nothing left to confirm.
+    pub fn select(&mut self, path: &str, option: &str) -> bool {
+        let chooser = match self.groups.get_mut(path) {
+            Some(chooser) => chooser,
+            None => return false,
+        };
+
+        let already_active = chooser.active.as_deref() == Some(option);
+        chooser.pending = if already_active {
+            None
+        } else {
+            Some(option.to_string())
+        };
+
+        already_active
+    }
+
+    /// Forgets every chooser, e.g. after reconnecting to a robot that may
+    /// publish different ones.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto_chooser() -> ChooserRegistry {
+        let mut choosers = ChooserRegistry::default();
+        let options = Value::Array(vec!["Left".into(), "Right".into()]);
+        choosers.update("/SmartDashboard/Auto/options", &options);
+        choosers.update("/SmartDashboard/Auto/active", &"Left".into());
+        choosers
+    }
+
+    fn pending(choosers: &ChooserRegistry) -> Option<String> {
+        choosers.list()[0].pending.clone()
+    }
+
+    #[test]
+    fn selections_are_pending_until_the_robot_confirms_them() {
+        let mut choosers = auto_chooser();
+
+        assert_eq!(
+            choosers.selection_topic("/SmartDashboard/Auto", "Right"),
+            Ok("/SmartDashboard/Auto/selected".to_string())
+        );
+        assert!(choosers
+            .selection_topic("/SmartDashboard/Auto", "Middle")
+            .is_err());
+        assert_eq!(pending(&choosers), None);
+
+        assert!(!choosers.select("/SmartDashboard/Auto", "Right"));
+        assert_eq!(pending(&choosers), Some("Right".to_string()));
+
+        let events = choosers.update("/SmartDashboard/Auto/active", &"Right".into());
+        assert!(events.contains(&ChooserEvent::Confirmed(ChooserConfirmed {
+            path: "/SmartDashboard/Auto".to_string(),
+            option: "Right".to_string(),
+        })));
+        assert_eq!(pending(&choosers), None);
+    }
+
+    #[test]
+    fn selections_the_robot_already_has_active_are_not_pending() {
+        let mut choosers = auto_chooser();
+
+        assert!(choosers.select("/SmartDashboard/Auto", "Left"));
+        assert_eq!(pending(&choosers), None);
+    }
+}
@@ -0,0 +1,278 @@
+use std::path::PathBuf;
+
+use network_tables::Value;
+use tauri::{Manager, State, Window};
+
+use super::chooser::{Chooser, ChooserConfirmed};
+use super::impact_detector::Impact;
+use super::match_context::MatchContext;
+use super::match_phase::MatchClock;
+use super::power_tracker::PowerStats;
+use super::replay::{Replay, ReplayCommand, ReplayStatus};
+use super::robot_address::TelemetryTarget;
+use super::subscription_sets::SubscriptionSet;
+use super::supervisor::TelemetrySupervisor;
+use super::telemetry_status::ConnectionState;
+use super::topic_catalog::TopicInfo;
+use super::value_cache::CachedValue;
+use super::watchdog::WatchdogConfig;
+
+/// Returns the robot telemetry is currently targeting.
+#[tauri::command]
+pub fn get_telemetry_target(supervisor: State<TelemetrySupervisor>) -> TelemetryTarget {
+    supervisor.target()
+}
+
+/// Returns the current state of the connection to the robot, for windows that
+/// missed the last `telemetry_status` event.
+#[tauri::command]
+pub fn get_telemetry_status(supervisor: State<TelemetrySupervisor>) -> ConnectionState {
+    supervisor.status()
+}
+
+/// Points telemetry at a different robot and reconnects.
+#[tauri::command]
+pub fn set_telemetry_target(supervisor: State<TelemetrySupervisor>, target: TelemetryTarget) {
+    supervisor.set_target(target);
+}
+
+/// Changes how long data may go without updating before it is marked stale.
+#[tauri::command]
+pub fn set_watchdog_config(supervisor: State<TelemetrySupervisor>, watchdog: WatchdogConfig) {
+    supervisor.set_watchdog(watchdog);
+}
+
+/// Drops the current connection and starts connecting again, e.g. after the
+/// robot code was redeployed. Also resumes paused telemetry.
+#[tauri::command]
+pub fn reconnect_telemetry(supervisor: State<TelemetrySupervisor>) {
+    tracing::info!("Reconnecting telemetry");
+    supervisor.restart();
+}
+
+/// Disconnects from the robot until telemetry is reconnected.
+#[tauri::command]
+pub fn pause_telemetry(supervisor: State<TelemetrySupervisor>) {
+    if supervisor.pause() {
+        tracing::info!("Telemetry paused");
+    }
+}
+
+/// Publishes a value to `name` under the dashboard's publish prefix, e.g.
+/// `/Jankboard/acc-profile`.
+///
+/// The topic is announced on first use, with its type inferred from `value`.
+#[tauri::command]
+pub async fn publish_value(
+    supervisor: State<'_, TelemetrySupervisor>,
+    name: String,
+    value: Value,
+) -> Result<(), String> {
+    supervisor
+        .shared()
+        .publisher
+        .publish(&name, value)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to publish {}: {}", name, e);
+            e.to_string()
+        })
+}
+
+/// Returns every `SendableChooser` the robot has published.
+#[tauri::command]
+pub fn list_choosers(supervisor: State<TelemetrySupervisor>) -> Vec<Chooser> {
+    supervisor.shared().choosers.lock().unwrap().list()
+}
+
+/// Selects `option` on the chooser at `path` by publishing it to the
+/// chooser's `selected` topic.
+///
+/// The choice is confirmed with a `chooser_confirmed` event once the robot
+/// reports it as `active`.
+#[tauri::command]
+pub async fn select_chooser_option(
+    window: Window,
+    supervisor: State<'_, TelemetrySupervisor>,
+    path: String,
+    option: String,
+) -> Result<(), String> {
+    let topic_name = supervisor
+        .shared()
+        .choosers
+        .lock()
+        .unwrap()
+        .selection_topic(&path, &option)?;
+
+    supervisor
+        .shared()
+        .publisher
+        .publish_absolute(&topic_name, Value::from(option.as_str()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // only pending once published, so a failed publish leaves nothing to
+    // confirm
+    let already_active = supervisor
+        .shared()
+        .choosers
+        .lock()
+        .unwrap()
+        .select(&path, &option);
+
+    if already_active {
+        window
+            .emit_all("chooser_confirmed", ChooserConfirmed { path, option })
+            .expect("Failed to emit chooser_confirmed event");
+    }
+
+    Ok(())
+}
+
+/// Returns everything known about the current match.
+#[tauri::command]
+pub fn get_match_context(supervisor: State<TelemetrySupervisor>) -> MatchContext {
+    supervisor.shared().match_context.lock().unwrap().clone()
+}
+
+/// Returns the sets of topics telemetry subscribes to.
+#[tauri::command]
+pub fn list_subscription_sets(supervisor: State<TelemetrySupervisor>) -> Vec<SubscriptionSet> {
+    supervisor.config().subscriptions
+}
+
+/// Adds a set of topics to subscribe to, or replaces the set with the same
+/// name, and resubscribes.
+#[tauri::command]
+pub fn add_subscription_set(
+    supervisor: State<TelemetrySupervisor>,
+    set: SubscriptionSet,
+) -> Result<(), String> {
+    if set.name.is_empty() {
+        return Err("Subscription sets need a name".to_string());
+    }
+    if set.topics.is_empty() {
+        return Err(format!("{} has no topics", set.name));
+    }
+
+    tracing::info!("Subscribing to {:?} as {}", set.topics, set.name);
+    supervisor.add_subscription_set(set);
+    Ok(())
+}
+
+/// Stops subscribing to the set called `name`.
+#[tauri::command]
+pub fn remove_subscription_set(
+    supervisor: State<TelemetrySupervisor>,
+    name: String,
+) -> Result<(), String> {
+    if supervisor.remove_subscription_set(&name) {
+        Ok(())
+    } else {
+        Err(format!("No subscription set called {}", name))
+    }
+}
+
+/// Returns every topic the robot has announced, with its type, properties and
+/// latest value.
+#[tauri::command]
+pub fn list_topics(supervisor: State<TelemetrySupervisor>) -> Vec<TopicInfo> {
+    supervisor.shared().topics.lock().unwrap().list()
+}
+
+/// Returns a single announced topic by its full name.
+#[tauri::command]
+pub fn get_topic(supervisor: State<TelemetrySupervisor>, name: String) -> Option<TopicInfo> {
+    supervisor.shared().topics.lock().unwrap().get(&name)
+}
+
+/// Returns the last value of every topic with when it was received and
+/// whether it is stale, so windows that open or reload can hydrate without
+/// waiting for each topic to change.
+#[tauri::command]
+pub fn get_telemetry_snapshot(supervisor: State<TelemetrySupervisor>) -> Vec<CachedValue> {
+    let link_stale = !matches!(
+        supervisor.status(),
+        ConnectionState::Live { .. } | ConnectionState::Replaying { .. }
+    );
+
+    let shared = supervisor.shared();
+    shared
+        .values
+        .lock()
+        .unwrap()
+        .snapshot(shared.now(), &supervisor.config().watchdog, link_stale)
+}
+
+/// Returns every impact detected this session, oldest first.
+#[tauri::command]
+pub fn get_impact_log(supervisor: State<TelemetrySupervisor>) -> Vec<Impact> {
+    supervisor.shared().impacts.lock().unwrap().clone()
+}
+
+/// Returns the brownout-range dips of this session with statistics about
+/// them.
+#[tauri::command]
+pub fn get_power_stats(supervisor: State<TelemetrySupervisor>) -> PowerStats {
+    supervisor.shared().power.lock().unwrap().stats()
+}
+
+/// Returns the current match phase and how long it and the match have been
+/// running.
+#[tauri::command]
+pub fn get_match_clock(supervisor: State<TelemetrySupervisor>) -> MatchClock {
+    let shared = supervisor.shared();
+    shared.match_phase.lock().unwrap().clock(shared.now())
+}
+
+/// Disconnects from the robot and replays the `.wpilog` recording at `path`
+/// instead, starting paused at its beginning.
+///
+/// Telemetry goes back to the robot once it is reconnected.
+#[tauri::command]
+pub async fn start_replay(
+    supervisor: State<'_, TelemetrySupervisor>,
+    path: PathBuf,
+) -> Result<(), String> {
+    let replay = Replay::load(&path).await.map_err(|e| {
+        tracing::warn!("{}", e);
+        e
+    })?;
+
+    supervisor.start_replay(replay);
+    Ok(())
+}
+
+/// Plays the running replay, from the start if it reached the end.
+#[tauri::command]
+pub fn play_replay(supervisor: State<TelemetrySupervisor>) -> Result<(), String> {
+    supervisor.control_replay(ReplayCommand::Play)
+}
+
+/// Pauses the running replay.
+#[tauri::command]
+pub fn pause_replay(supervisor: State<TelemetrySupervisor>) -> Result<(), String> {
+    supervisor.control_replay(ReplayCommand::Pause)
+}
+
+/// Jumps to `position_ms` milliseconds into the running replay.
+#[tauri::command]
+pub fn seek_replay(supervisor: State<TelemetrySupervisor>, position_ms: u64) -> Result<(), String> {
+    supervisor.control_replay(ReplayCommand::Seek(position_ms))
+}
+
+/// Plays the running replay at `speed` times real time.
+#[tauri::command]
+pub fn set_replay_speed(supervisor: State<TelemetrySupervisor>, speed: f64) -> Result<(), String> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!("{} is not a playback speed", speed));
+    }
+
+    supervisor.control_replay(ReplayCommand::Speed(speed))
+}
+
+/// Returns where the running replay is, if there is one.
+#[tauri::command]
+pub fn get_replay_status(supervisor: State<TelemetrySupervisor>) -> Option<ReplayStatus> {
+    supervisor.shared().replay.lock().unwrap().clone()
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::battery_monitor::BatteryConfig;
+use super::impact_detector::ImpactConfig;
+use super::recorder::RecorderConfig;
+use super::retry_policy::RetryPolicy;
+use super::robot_address::TelemetryTarget;
+use super::speed_governor::SpeedLimitConfig;
+use super::subscription_sets::{default_subscription_sets, SubscriptionSet};
+use super::triggers::{default_trigger_rules, TriggerRule};
+use super::watchdog::WatchdogConfig;
+
+/// Name of the telemetry config file inside the app config directory.
+const CONFIG_FILE_NAME: &str = "telemetry.json";
+
+/// Name of the directory recordings go to inside the app data directory.
+const RECORDINGS_DIR_NAME: &str = "recordings";
+
+/// Whether and how every received message is emitted as it is, on top of the
+/// telemetry state.
+///
+/// The dashboard only reads `telemetry_state` and `get_telemetry_snapshot`, so
+/// raw messages are off unless a window or tool needs every value, like a
+/// topic inspector or a plot of a topic the state doesn't carry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawMessages {
+    #[default]
+    Off,
+    /// Each message is emitted as a `telemetry_data` event.
+    Each,
+    /// The messages of a UI frame are emitted together as one
+    /// `telemetry_batch` event.
+    Batched,
+}
+
+/// Everything needed to start a telemetry session.
+///
+/// `connect_retry` paces attempts to reach the robot, and
+/// `subscribe_retry` paces attempts to subscribe once connected. Values
+/// published from the dashboard go under `publish_prefix`. `subscriptions`
+/// lists the topics to subscribe to. The telemetry state is emitted to the
+/// frontend at most `ui_frame_rate` times per second, and `raw_messages`
+/// decides whether the messages it is built from are emitted too.
+/// `triggers` are the rules deciding which events are emitted as values
+/// change, and `battery`, `impacts` and `speed_limits` tune the battery,
+/// impact and overspeed alerts. `recorder` decides when sessions are recorded
+/// and where to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub target: TelemetryTarget,
+    pub watchdog: WatchdogConfig,
+    pub connect_retry: RetryPolicy,
+    pub subscribe_retry: RetryPolicy,
+    pub publish_prefix: String,
+    pub subscriptions: Vec<SubscriptionSet>,
+    pub ui_frame_rate: f64,
+    pub raw_messages: RawMessages,
+    pub triggers: Vec<TriggerRule>,
+    pub battery: BatteryConfig,
+    pub impacts: ImpactConfig,
+    pub speed_limits: SpeedLimitConfig,
+    pub recorder: RecorderConfig,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            target: TelemetryTarget::default(),
+            watchdog: WatchdogConfig::default(),
+            connect_retry: RetryPolicy::default(),
+            subscribe_retry: RetryPolicy {
+                max_attempts: Some(50),
+                ..Default::default()
+            },
+            publish_prefix: "/Jankboard/".to_string(),
+            subscriptions: default_subscription_sets(),
+            ui_frame_rate: 30.0,
+            raw_messages: RawMessages::default(),
+            triggers: default_trigger_rules(),
+            battery: BatteryConfig::default(),
+            impacts: ImpactConfig::default(),
+            speed_limits: SpeedLimitConfig::default(),
+            recorder: RecorderConfig::default(),
+        }
+    }
+}
+
+/// Returns the path of the telemetry config file, if the app config
+/// directory is known.
+pub fn config_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle
+        .path_resolver()
+        .app_config_dir()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+/// Returns the directory recordings go to when none is configured, if the app
+/// data directory is known.
+pub fn recording_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(RECORDINGS_DIR_NAME))
+}
+
+/// Loads the telemetry config from `telemetry.json` in the app config
+/// directory.
+///
+/// Missing fields take their default values. If the file doesn't exist or
+/// can't be parsed, the defaults are used for everything. Trigger rules with
+/// an invalid event name are dropped. Environment variables are applied on
+/// top, see [`TelemetryTarget::apply_env`].
+pub fn load_config(app_handle: &AppHandle) -> TelemetryConfig {
+    let mut config = match config_path(app_handle) {
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str(&contents) {
+                Ok(config) => config,
+                Err(e) => {
+                    tracing::warn!("Failed to parse {}: {}", path.display(), e);
+                    TelemetryConfig::default()
+                }
+            },
+            Err(_) => {
+                tracing::info!("No config at {}, using defaults", path.display());
+                TelemetryConfig::default()
+            }
+        },
+        None => TelemetryConfig::default(),
+    };
+
+    config.triggers.retain(|rule| match rule.validate() {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::error!("Ignoring trigger rule: {}", e);
+            false
+        }
+    });
+    config.target.apply_env();
+    config
+}
@@ -1,51 +1,155 @@
 use network_tables::v4::client_config::Config;
 use network_tables::v4::Client;
-use std::net::{Ipv4Addr, SocketAddrV4};
-use tauri::{AppHandle, Manager};
-use tokio::time::{sleep, Duration};
+use std::net::SocketAddrV4;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+
+use super::retry_policy::RetryPolicy;
+use super::robot_address::{resolve, RobotAddress, TelemetryTarget};
+use super::telemetry_status::{ConnectionState, TelemetryStatus};
+use super::topic_catalog::TopicCatalog;
+
+/// A client along with the address it connected on.
+pub struct ConnectedClient {
+    pub client: Arc<Client>,
+    pub candidate: RobotAddress,
+    pub address: SocketAddrV4,
+}
 
 /// Creates a NetworkTables client
 ///
-/// This function will keep trying to create a NetworkTables client with the given
-/// IP address and port until it is successful. It will sleep for 3 seconds between
-/// attempts. If successful, it will emit the `telemetry_connected` event on the
-/// `app_handle` with the payload `"connected"`. If unsuccessful, it will emit the
-/// `telemetry_status` event with the payload `"disconnected"` instead.
+/// This function dials every candidate address of `target` at the same time
+/// and keeps the first client whose NT4 handshake succeeds. The remaining
+/// attempts are aborted. If every candidate fails, it will back off according
+/// to `retry_policy` before racing them again, and give up once the policy runs
+/// out of attempts.
+///
+/// Topic announcements made to the client are tracked in `catalog`.
+///
+/// Progress is reported through `status`, which moves through
+/// [`ConnectionState::Resolving`] and [`ConnectionState::Connecting`], and
+/// into [`ConnectionState::Backoff`] whenever a round of attempts fails.
 pub async fn create_client(
-    app_handle: &AppHandle,
-    ntable_ip: &Ipv4Addr,
-    ntable_port: &u16,
-) -> Client {
+    status: &Arc<TelemetryStatus>,
+    catalog: &Arc<Mutex<TopicCatalog>>,
+    target: &TelemetryTarget,
+    retry_policy: &RetryPolicy,
+) -> Option<ConnectedClient> {
+    let mut backoff = retry_policy.backoff();
+
     loop {
-        let client_attempt = Client::try_new_w_config(
-            SocketAddrV4::new(*ntable_ip, *ntable_port),
-            Config {
-                ..Default::default()
-            },
-        )
-        .await;
+        status.set(ConnectionState::Resolving);
+
+        if let Some(connected) = race_candidates(status, catalog, target).await {
+            tracing::info!(
+                "Client created on {} ({})",
+                connected.candidate,
+                connected.address
+            );
+            return Some(connected);
+        }
+
+        let delay = backoff.next_delay()?;
+
+        tracing::debug!(
+            message = "No robot address answered. Retrying...",
+            team = target.team_number,
+            attempt = backoff.attempt(),
+            retry_in_ms = delay.as_millis() as u64,
+        );
+        status.set(ConnectionState::Backoff {
+            attempt: backoff.attempt(),
+            max_attempts: backoff.max_attempts(),
+            retry_in: delay.as_millis() as u64,
+        });
+
+        sleep(delay).await;
+    }
+}
+
+/// Dials every candidate in parallel and returns the first one that connects.
+///
+/// Returns `None` once every attempt has failed.
+async fn race_candidates(
+    status: &Arc<TelemetryStatus>,
+    catalog: &Arc<Mutex<TopicCatalog>>,
+    target: &TelemetryTarget,
+) -> Option<ConnectedClient> {
+    let mut attempts = JoinSet::new();
+
+    for candidate in target.candidates() {
+        let port = target.port;
+        let status = status.clone();
+        let catalog = catalog.clone();
+        attempts.spawn(async move { try_candidate(&status, catalog, candidate, port).await });
+    }
+
+    while let Some(attempt) = attempts.join_next().await {
+        if let Ok(Some(connected)) = attempt {
+            // the losing attempts are no longer needed
+            attempts.abort_all();
+            return Some(connected);
+        }
+    }
+
+    None
+}
+
+/// Resolves a single candidate and tries each of its addresses in turn.
+async fn try_candidate(
+    status: &TelemetryStatus,
+    catalog: Arc<Mutex<TopicCatalog>>,
+    candidate: RobotAddress,
+    port: u16,
+) -> Option<ConnectedClient> {
+    let addresses = resolve(&candidate, port).await;
+
+    if !addresses.is_empty() {
+        status.set(ConnectionState::Connecting);
+    }
+
+    for address in addresses {
+        let client_attempt = Client::try_new_w_config(address, client_config(&catalog)).await;
 
         match client_attempt {
             Ok(client) => {
-                println!("Client created");
-                app_handle
-                    .emit_all("telemetry_status", "connected")
-                    .expect("Failed to emit telemetry_status connected event");
-                break client; // Exit the loop if the client is successfully created
+                return Some(ConnectedClient {
+                    client: Arc::new(client),
+                    candidate,
+                    address,
+                })
             }
             Err(e) => {
                 tracing::debug!(
-                    message = "Failed to create client. Retrying in 3 seconds...",
+                    message = "Failed to create client",
+                    address = %address,
                     error = %e,
-                    rate_limit = "3s",
                 );
-                app_handle
-                    .emit_all("telemetry_status", "disconnected")
-                    .expect("Failed to emit telemetry_status disconnected event");
+            }
+        }
+    }
+
+    None
+}
+
+/// Builds the client config, keeping `catalog` up to date with the topics the
+/// server announces.
+fn client_config(catalog: &Arc<Mutex<TopicCatalog>>) -> Config {
+    let announced = catalog.clone();
+    let unannounced = catalog.clone();
 
-                sleep(Duration::from_secs(3)).await; // Wait for 3 seconds before retrying
-                continue; // Continue the loop to retry
+    Config {
+        on_announce: Box::new(move |topic| {
+            announced.lock().unwrap().announce(topic);
+            Box::pin(async {})
+        }),
+        on_un_announce: Box::new(move |topic| {
+            if let Some(topic) = topic {
+                unannounced.lock().unwrap().unannounce(&topic.name);
             }
-        };
+            Box::pin(async {})
+        }),
+        ..Default::default()
     }
 }
@@ -1,47 +1,96 @@
-use network_tables::v4::{Client, Subscription, SubscriptionOptions};
-use tokio::time::{sleep, Duration};
+use network_tables::v4::{Client, MessageData, Subscription};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::sleep;
 
-/// Create a subscription to all SmartDashboard values
-///
-/// The subscription will receive updates to all values in the
-/// SmartDashboard, and any future values added to it.
-///
-/// The subscription will be created with the following options:
+use super::retry_policy::RetryPolicy;
+use super::subscription_sets::SubscriptionSet;
+use super::telemetry_status::{ConnectionState, TelemetryStatus};
+
+/// How many messages may be queued between the subscriptions and the
+/// telemetry loop.
+const MESSAGE_BUFFER: usize = 1024;
+
+/// Create a subscription to the topics of a subscription set
 ///
-/// * `all`: `true` - receive updates to all values
-/// * `prefix`: `true` - receive updates to all keys with the
-///   prefix `/SmartDashboard`
+/// The subscription will receive updates to all values matched by `set`,
+/// and any future values added to it, with the options given by
+/// [`SubscriptionSet::options`].
 ///
-/// This function will retry creating a subscription every 3 seconds
-/// if it fails.
-pub async fn create_subscription(client: &Client) -> Result<Subscription, network_tables::Error> {
-    let mut attempts: u8 = 0;
+/// This function will retry creating a subscription according to
+/// `retry_policy` if it fails, reporting each wait through `status`.
+pub async fn create_subscription(
+    client: &Client,
+    set: &SubscriptionSet,
+    status: &TelemetryStatus,
+    retry_policy: &RetryPolicy,
+) -> Result<Subscription, network_tables::Error> {
+    let mut backoff = retry_policy.backoff();
 
     loop {
         let subscription_attempt = client
-            .subscribe_w_options(
-                &["/SmartDashboard"],
-                Some(SubscriptionOptions {
-                    all: Some(true),
-                    prefix: Some(true),
-                    ..Default::default()
-                }),
-            )
+            .subscribe_w_options(set.topics.as_slice(), Some(set.options()))
             .await;
 
         match subscription_attempt {
             Ok(subscription) => break Ok(subscription),
             Err(e) => {
-                tracing::debug!("Failed to create subscription: {:?}", e);
+                tracing::debug!("Failed to create subscription {}: {:?}", set.name, e);
 
-                if attempts >= 50 {
-                    break Err(e);
-                }
+                let delay = match backoff.next_delay() {
+                    Some(delay) => delay,
+                    None => break Err(e),
+                };
 
-                attempts += 1;
-                sleep(Duration::from_secs(3)).await; // Wait for 3 seconds before retrying
+                status.set(ConnectionState::Backoff {
+                    attempt: backoff.attempt(),
+                    max_attempts: backoff.max_attempts(),
+                    retry_in: delay.as_millis() as u64,
+                });
+
+                sleep(delay).await;
                 continue;
             }
         }
     }
 }
+
+/// Creates one subscription for each of `sets`, failing if any of them fails.
+pub async fn create_subscriptions(
+    client: &Client,
+    sets: &[SubscriptionSet],
+    status: &TelemetryStatus,
+    retry_policy: &RetryPolicy,
+) -> Result<Vec<Subscription>, network_tables::Error> {
+    let mut subscriptions = Vec::with_capacity(sets.len());
+
+    for set in sets {
+        subscriptions.push(create_subscription(client, set, status, retry_policy).await?);
+    }
+
+    Ok(subscriptions)
+}
+
+/// Forwards the messages of every subscription into a single channel.
+///
+/// The channel closes once every subscription has ended. Dropping the
+/// returned set stops the forwarding.
+pub fn merge_subscriptions(
+    subscriptions: Vec<Subscription>,
+) -> (JoinSet<()>, mpsc::Receiver<MessageData>) {
+    let (sender, receiver) = mpsc::channel(MESSAGE_BUFFER);
+    let mut forwarders = JoinSet::new();
+
+    for mut subscription in subscriptions {
+        let sender = sender.clone();
+        forwarders.spawn(async move {
+            while let Some(message) = subscription.next().await {
+                if sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    (forwarders, receiver)
+}
@@ -0,0 +1,71 @@
+use network_tables::Value;
+use serde::Serialize;
+
+use super::epoch::epoch_ms;
+use super::telemetry_state::{Gear, Mode};
+
+/// A transition of the robot's gear or acceleration profile, as emitted in
+/// the `drive_state_changed` event.
+///
+/// `timestamp` is when the transition was received, in milliseconds since the
+/// Unix epoch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DriveStateChange {
+    Gear {
+        from: Gear,
+        to: Gear,
+        timestamp: u64,
+    },
+    AccProfile {
+        from: Mode,
+        to: Mode,
+        timestamp: u64,
+    },
+}
+
+/// Notices when the robot shifts gears or changes acceleration profile.
+///
+/// Only values published by the robot count. The first value of each topic is
+/// taken as the starting state rather than a transition, and the tracker
+/// outlives reconnections, so reconnecting only reports a transition if the
+/// robot really changed state in the meantime.
+#[derive(Default)]
+pub struct DriveStateTracker {
+    gear: Option<Gear>,
+    acc_profile: Option<Mode>,
+}
+
+impl DriveStateTracker {
+    /// Applies an update to the topic `topic_name`, which is the display name
+    /// the frontend knows it by.
+    ///
+    /// Returns the transition the update made, if any.
+    pub fn update(&mut self, topic_name: &str, value: &Value) -> Option<DriveStateChange> {
+        match topic_name {
+            "gear" => {
+                let to = value.as_str().and_then(Gear::from_name)?;
+                match self.gear.replace(to) {
+                    Some(from) if from != to => Some(DriveStateChange::Gear {
+                        from,
+                        to,
+                        timestamp: epoch_ms(),
+                    }),
+                    _ => None,
+                }
+            }
+            "acc-profile" => {
+                let to = value.as_str().and_then(Mode::from_name)?;
+                match self.acc_profile.replace(to) {
+                    Some(from) if from != to => Some(DriveStateChange::AccProfile {
+                        from,
+                        to,
+                        timestamp: epoch_ms(),
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::time::{Duration, Instant};
+
+use network_tables::Value;
+use serde::{Deserialize, Serialize};
+
+use super::epoch::epoch_ms;
+
+/// Thresholds used by the [`ImpactDetector`].
+///
+/// Accelerations are in g and jerk in g/s, as published by the robot. A
+/// collision is a horizontal acceleration above `collision_accel` or a jerk
+/// above `collision_jerk`. A rapid deceleration is an acceleration of more
+/// than `deceleration_accel` against the direction of travel while moving
+/// faster than `min_speed` (m/s). After an impact is detected, further ones of
+/// the same kind are ignored for `refractory_ms`, since a single hit shows up
+/// in several samples. Rapid decelerations are ignored during a collision's
+/// refractory period too, since the hit is what slowed the robot down.
+///
+/// A collision is imminent when the distance to the nearest obstacle, which
+/// the robot publishes on `distance_topic` in meters, shrinks faster than
+/// `min_speed` and would reach zero within `imminent_ms` at that speed. The
+/// warning has its own refractory period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImpactConfig {
+    pub collision_accel: f64,
+    pub collision_jerk: f64,
+    pub deceleration_accel: f64,
+    pub min_speed: f64,
+    pub refractory_ms: u64,
+    pub distance_topic: String,
+    pub imminent_ms: u64,
+}
+
+impl Default for ImpactConfig {
+    fn default() -> Self {
+        Self {
+            collision_accel: 2.5,
+            collision_jerk: 60.0,
+            deceleration_accel: 1.0,
+            min_speed: 0.5,
+            refractory_ms: 1000,
+            distance_topic: "obstacle-distance".to_string(),
+            imminent_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpactKind {
+    Collision,
+    RapidDeceleration,
+}
+
+/// A single impact, as emitted in the `impact` event and kept in the session
+/// log.
+///
+/// `direction` is the direction of the acceleration in degrees, counter
+/// clockwise from the robot's x axis. `timestamp` is in milliseconds since the
+/// Unix epoch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Impact {
+    pub kind: ImpactKind,
+    pub magnitude: f64,
+    pub jerk: f64,
+    pub direction: f64,
+    pub speed: f64,
+    pub timestamp: u64,
+}
+
+/// The payload of the `collision_imminent` event.
+///
+/// `distance` is in meters and `closing_speed` in m/s. `time_to_collision_ms`
+/// is how long the robot would take to reach the obstacle at that speed, and
+/// `timestamp` is in milliseconds since the Unix epoch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CollisionWarning {
+    pub distance: f64,
+    pub closing_speed: f64,
+    pub time_to_collision_ms: u64,
+    pub timestamp: u64,
+}
+
+/// Something noticed by the [`ImpactDetector`].
+pub enum ImpactEvent {
+    Impact(Impact),
+    /// The robot is about to hit an obstacle.
+    Imminent(CollisionWarning),
+}
+
+/// Detects collisions and rapid decelerations from the accelerometer, jerk
+/// and chassis speed topics, and warns of collisions about to happen from the
+/// obstacle distance topic.
+pub struct ImpactDetector {
+    config: ImpactConfig,
+    speed: (f64, f64),
+    accel: (f64, f64),
+    jerk: (f64, f64),
+    distance: Option<(f64, Instant)>,
+    last_collision: Option<Instant>,
+    last_deceleration: Option<Instant>,
+    last_warning: Option<Instant>,
+}
+
+impl ImpactDetector {
+    pub fn new(config: ImpactConfig) -> Self {
+        Self {
+            config,
+            speed: (0.0, 0.0),
+            accel: (0.0, 0.0),
+            jerk: (0.0, 0.0),
+            distance: None,
+            last_collision: None,
+            last_deceleration: None,
+            last_warning: None,
+        }
+    }
+
+    /// Applies an update to the topic `topic_name`, which is the display name
+    /// the frontend knows it by.
+    ///
+    /// Returns the impacts and warnings the update revealed.
+    pub fn update(&mut self, topic_name: &str, value: &Value, now: Instant) -> Vec<ImpactEvent> {
+        let number = match value.as_f64() {
+            Some(number) => number,
+            None => return Vec::new(),
+        };
+
+        match topic_name {
+            "chassis-x-speed" => self.speed.0 = number,
+            "chassis-y-speed" => self.speed.1 = number,
+            "accx" => self.accel.0 = number,
+            "accy" => self.accel.1 = number,
+            "jerk-x" => self.jerk.0 = number,
+            "jerk-y" => self.jerk.1 = number,
+            topic if topic == self.config.distance_topic => {
+                return self
+                    .update_distance(number, now)
+                    .map(ImpactEvent::Imminent)
+                    .into_iter()
+                    .collect();
+            }
+            _ => return Vec::new(),
+        }
+
+        let mut impacts = Vec::new();
+
+        let magnitude = self.accel.0.hypot(self.accel.1);
+        let jerk = self.jerk.0.hypot(self.jerk.1);
+        if (magnitude > self.config.collision_accel || jerk > self.config.collision_jerk)
+            && self.ready(self.last_collision, now)
+        {
+            self.last_collision = Some(now);
+            impacts.push(ImpactEvent::Impact(
+                self.impact(ImpactKind::Collision, magnitude),
+            ));
+        }
+
+        let speed = self.speed.0.hypot(self.speed.1);
+        if speed > self.config.min_speed {
+            // the component of the acceleration against the direction of travel
+            let deceleration = -(self.accel.0 * self.speed.0 + self.accel.1 * self.speed.1) / speed;
+            if deceleration > self.config.deceleration_accel
+                && self.ready(self.last_deceleration, now)
+                && self.ready(self.last_collision, now)
+            {
+                self.last_deceleration = Some(now);
+                impacts.push(ImpactEvent::Impact(
+                    self.impact(ImpactKind::RapidDeceleration, deceleration),
+                ));
+            }
+        }
+
+        impacts
+    }
+
+    /// Estimates the closing speed from the previous distance, and warns if
+    /// the obstacle would be reached within `imminent_ms`.
+    fn update_distance(&mut self, distance: f64, now: Instant) -> Option<CollisionWarning> {
+        let (previous, since) = self.distance.replace((distance, now))?;
+        let elapsed = now.duration_since(since).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        let closing_speed = (previous - distance) / elapsed;
+        if closing_speed <= self.config.min_speed {
+            return None;
+        }
+
+        let time_to_collision = Duration::from_secs_f64(distance.max(0.0) / closing_speed);
+        if time_to_collision > Duration::from_millis(self.config.imminent_ms)
+            || !self.ready(self.last_warning, now)
+        {
+            return None;
+        }
+
+        self.last_warning = Some(now);
+        Some(CollisionWarning {
+            distance,
+            closing_speed,
+            time_to_collision_ms: time_to_collision.as_millis() as u64,
+            timestamp: epoch_ms(),
+        })
+    }
+
+    /// Returns whether the refractory period since `last` is over.
+    fn ready(&self, last: Option<Instant>, now: Instant) -> bool {
+        last.map_or(true, |last| {
+            now.duration_since(last) >= Duration::from_millis(self.config.refractory_ms)
+        })
+    }
+
+    fn impact(&self, kind: ImpactKind, magnitude: f64) -> Impact {
+        Impact {
+            kind,
+            magnitude,
+            jerk: self.jerk.0.hypot(self.jerk.1),
+            direction: self.accel.1.atan2(self.accel.0).to_degrees(),
+            speed: self.speed.0.hypot(self.speed.1),
+            timestamp: epoch_ms(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(events: Vec<ImpactEvent>) -> Vec<ImpactKind> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                ImpactEvent::Impact(impact) => Some(impact.kind),
+                ImpactEvent::Imminent(_) => None,
+            })
+            .collect()
+    }
+
+    fn warnings(events: Vec<ImpactEvent>) -> Vec<CollisionWarning> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                ImpactEvent::Imminent(warning) => Some(warning),
+                ImpactEvent::Impact(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collisions_suppress_rapid_decelerations() {
+        let mut detector = ImpactDetector::new(ImpactConfig::default());
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        detector.update("chassis-x-speed", &Value::F64(3.0), at(0));
+        // a hit from the front decelerates hard enough for both
+        assert_eq!(
+            kinds(detector.update("accx", &Value::F64(-3.0), at(0))),
+            [ImpactKind::Collision]
+        );
+        assert!(detector
+            .update("accx", &Value::F64(-1.5), at(500))
+            .is_empty());
+        assert_eq!(
+            kinds(detector.update("accx", &Value::F64(-1.5), at(1000))),
+            [ImpactKind::RapidDeceleration]
+        );
+    }
+
+    #[test]
+    fn warns_when_an_obstacle_is_about_to_be_reached() {
+        let mut detector = ImpactDetector::new(ImpactConfig::default());
+        let start = Instant::now();
+        let mut distance = |meters: f64, ms: u64| {
+            let now = start + Duration::from_millis(ms);
+            warnings(detector.update("obstacle-distance", &Value::F64(meters), now))
+        };
+
+        assert!(distance(5.0, 0).is_empty());
+        // closing at 2 m/s, 2 s away
+        assert!(distance(4.0, 500).is_empty());
+        // closing at 2 m/s, under a second away
+        let warning = distance(1.5, 1750).remove(0);
+        assert_eq!(warning.closing_speed, 2.0);
+        assert_eq!(warning.time_to_collision_ms, 750);
+        // still closing in, but warned already
+        assert!(distance(1.0, 2000).is_empty());
+        // backing away
+        assert!(distance(2.0, 3000).is_empty());
+        assert_eq!(distance(0.5, 3500).len(), 1);
+    }
+}
@@ -0,0 +1,123 @@
+use network_tables::Value;
+use serde::Serialize;
+
+/// The prefix the DriverStation and FMS publish match information under.
+pub const FMS_INFO_PREFIX: &str = "/FMSInfo/";
+
+/// The state of the robot as decoded from the FMS control word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ControlWord {
+    pub enabled: bool,
+    pub autonomous: bool,
+    pub test: bool,
+    pub emergency_stop: bool,
+    pub fms_attached: bool,
+    pub ds_attached: bool,
+}
+
+impl ControlWord {
+    /// Decodes the bit field published to `/FMSInfo/FMSControlData`.
+    pub fn from_bits(bits: u32) -> Self {
+        Self {
+            enabled: bits & 0x01 != 0,
+            autonomous: bits & 0x02 != 0,
+            test: bits & 0x04 != 0,
+            emergency_stop: bits & 0x08 != 0,
+            fms_attached: bits & 0x10 != 0,
+            ds_attached: bits & 0x20 != 0,
+        }
+    }
+}
+
+/// The kind of match being played.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchType {
+    #[default]
+    None,
+    Practice,
+    Qualification,
+    Elimination,
+}
+
+impl MatchType {
+    fn from_number(number: u32) -> Self {
+        match number {
+            1 => MatchType::Practice,
+            2 => MatchType::Qualification,
+            3 => MatchType::Elimination,
+            _ => MatchType::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alliance {
+    Red,
+    #[default]
+    Blue,
+}
+
+/// Everything known about the current match, as published under `/FMSInfo`.
+///
+/// This is the payload of the `match_context` event.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MatchContext {
+    pub event_name: String,
+    pub game_specific_message: String,
+    pub match_type: MatchType,
+    pub match_number: u32,
+    pub replay_number: u32,
+    pub alliance: Alliance,
+    pub station: u32,
+    pub control: ControlWord,
+}
+
+impl MatchContext {
+    /// Applies an update to the full topic name `topic_name`.
+    ///
+    /// Returns whether the context changed. Topics outside of `/FMSInfo` are
+    /// ignored.
+    pub fn update(&mut self, topic_name: &str, value: &Value) -> bool {
+        let key = match topic_name.strip_prefix(FMS_INFO_PREFIX) {
+            Some(key) => key,
+            None => return false,
+        };
+        let before = self.clone();
+
+        match key {
+            "EventName" => self.event_name = as_string(value).unwrap_or_default(),
+            "GameSpecificMessage" => {
+                self.game_specific_message = as_string(value).unwrap_or_default()
+            }
+            "MatchType" => self.match_type = MatchType::from_number(as_u32(value)),
+            "MatchNumber" => self.match_number = as_u32(value),
+            "ReplayNumber" => self.replay_number = as_u32(value),
+            "IsRedAlliance" => {
+                self.alliance = match value.as_bool() {
+                    Some(true) => Alliance::Red,
+                    _ => Alliance::Blue,
+                }
+            }
+            "StationNumber" => self.station = as_u32(value),
+            "FMSControlData" => self.control = ControlWord::from_bits(as_u32(value)),
+            _ => {}
+        }
+
+        *self != before
+    }
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+/// Reads a number that may have been published as either an integer or a
+/// double.
+fn as_u32(value: &Value) -> u32 {
+    value
+        .as_f64()
+        .map(|number| number as u32)
+        .unwrap_or_default()
+}
@@ -0,0 +1,120 @@
+use std::time::Instant;
+
+use serde::Serialize;
+
+use super::match_context::ControlWord;
+
+/// The phase of the match the robot is in, as derived from the control word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchPhase {
+    #[default]
+    Disabled,
+    Autonomous,
+    Teleop,
+    Test,
+    EmergencyStopped,
+}
+
+impl MatchPhase {
+    pub fn from_control(control: &ControlWord) -> Self {
+        if control.emergency_stop {
+            MatchPhase::EmergencyStopped
+        } else if !control.enabled {
+            MatchPhase::Disabled
+        } else if control.test {
+            MatchPhase::Test
+        } else if control.autonomous {
+            MatchPhase::Autonomous
+        } else {
+            MatchPhase::Teleop
+        }
+    }
+}
+
+/// The payload of the `match_phase_changed` event.
+///
+/// `match_elapsed_ms` is the time since the match started, if it has.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchPhaseChange {
+    pub from: MatchPhase,
+    pub to: MatchPhase,
+    pub match_elapsed_ms: Option<u64>,
+}
+
+/// The running match clock, as returned by `get_match_clock`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchClock {
+    pub phase: MatchPhase,
+    pub phase_elapsed_ms: u64,
+    pub match_elapsed_ms: Option<u64>,
+}
+
+/// Follows the robot through the phases of a match and times them.
+///
+/// A match starts when the robot is enabled in autonomous, or in teleop if it
+/// wasn't enabled in autonomous first, like in practice. The match clock keeps
+/// running through the disabled gap between autonomous and teleop, and ends
+/// when teleop does.
+pub struct MatchPhaseTracker {
+    phase: MatchPhase,
+    phase_started: Instant,
+    match_started: Option<Instant>,
+    match_ended: Option<Instant>,
+}
+
+impl MatchPhaseTracker {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            phase: MatchPhase::default(),
+            phase_started: now,
+            match_started: None,
+            match_ended: None,
+        }
+    }
+
+    /// Applies a new control word, returning the transition it made, if any.
+    pub fn update(&mut self, control: &ControlWord, now: Instant) -> Option<MatchPhaseChange> {
+        let phase = MatchPhase::from_control(control);
+        if phase == self.phase {
+            return None;
+        }
+
+        let in_match = self.match_started.is_some() && self.match_ended.is_none();
+        let starts_match = match phase {
+            MatchPhase::Autonomous => true,
+            MatchPhase::Teleop => !in_match,
+            _ => false,
+        };
+        if starts_match {
+            self.match_started = Some(now);
+            self.match_ended = None;
+        } else if self.phase == MatchPhase::Teleop && in_match {
+            self.match_ended = Some(now);
+        }
+
+        let from = self.phase;
+        self.phase = phase;
+        self.phase_started = now;
+
+        Some(MatchPhaseChange {
+            from,
+            to: phase,
+            match_elapsed_ms: self.match_elapsed_ms(now),
+        })
+    }
+
+    pub fn clock(&self, now: Instant) -> MatchClock {
+        MatchClock {
+            phase: self.phase,
+            phase_elapsed_ms: now.duration_since(self.phase_started).as_millis() as u64,
+            match_elapsed_ms: self.match_elapsed_ms(now),
+        }
+    }
+
+    fn match_elapsed_ms(&self, now: Instant) -> Option<u64> {
+        let end = self.match_ended.unwrap_or(now);
+        self.match_started
+            .map(|started| end.duration_since(started).as_millis() as u64)
+    }
+}
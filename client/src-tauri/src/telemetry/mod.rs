@@ -1,96 +1,152 @@
-use std::net::Ipv4Addr;
+use std::time::Instant;
 
-use network_tables::v4::{MessageData, Subscription};
-use serde_json::to_string;
-use tauri::{AppHandle, Manager};
-mod check_triggers;
+use tauri::AppHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+mod battery_monitor;
+mod chooser;
+pub mod commands;
+mod config;
 mod create_client;
 mod create_subscription;
+mod drive_state;
+mod epoch;
+mod impact_detector;
+mod match_context;
+mod match_phase;
+mod pipeline;
+mod power_tracker;
+mod proto_schema;
+mod publisher;
+mod recorder;
+mod replay;
+mod retry_policy;
+mod robot_address;
+mod shared;
+mod speed_governor;
+mod struct_schema;
+mod subscription_sets;
+mod supervisor;
+mod telemetry_state;
+mod telemetry_status;
+mod topic_catalog;
+mod triggers;
+mod value_cache;
+mod value_conversion;
+mod watchdog;
+mod wpilog;
 
-use check_triggers::check_triggers;
+pub use config::{load_config, TelemetryConfig};
 use create_client::create_client;
-use create_subscription::create_subscription;
+use create_subscription::{create_subscriptions, merge_subscriptions};
+use pipeline::TelemetryPipeline;
+use recorder::Recorder;
+use shared::TelemetryShared;
+pub use supervisor::TelemetrySupervisor;
+use telemetry_status::ConnectionState;
+use topic_catalog::catalog_subscription_sets;
+
+/// How often the freshness deadlines are checked.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Attempts to subscribe to NetworkTables topics and send the data to the frontend.
 ///
-/// This function creates a NetworkTables client and subscribes to all
-/// topics. When new data is received, it is serialized as JSON and emitted
-/// to all connected frontends using the "telemetry_data" event.
+/// This function creates a NetworkTables client on whichever candidate address
+/// of `config.target` answers first, subscribes to `config.subscriptions` and
+/// the topic catalog and schemas, and feeds every message to a
+/// [`TelemetryPipeline`]. The state of the connection is reported through
+/// `shared.status`, and the client is handed to `shared.publisher` for as long
+/// as the connection lasts.
 ///
-/// The function loops forever, retrying connection every 3 seconds, reconnecting if the client disconnects.
-pub async fn subscribe_topics(app_handle: AppHandle, ntable_ip: Ipv4Addr, ntable_port: u16) {
-    let mut previous_gpws: bool = false;
+/// The function loops forever, retrying connection according to
+/// `config.connect_retry` and reconnecting if the client disconnects. It only
+/// returns if the retry policy runs out of attempts.
+pub async fn subscribe_topics(
+    app_handle: AppHandle,
+    config: TelemetryConfig,
+    shared: TelemetryShared,
+) {
+    let status = &shared.status;
+    let publisher = &shared.publisher;
+
+    let mut subscription_sets = config.subscriptions.clone();
+    subscription_sets.extend(catalog_subscription_sets());
+    subscription_sets.push(struct_schema::schema_subscription_set());
+    subscription_sets.push(proto_schema::schema_subscription_set());
+
+    let recorder = Recorder::new(config.recorder.clone(), config::recording_dir(&app_handle));
+    let mut pipeline =
+        TelemetryPipeline::new(app_handle, config.clone(), shared.clone(), Some(recorder));
+    let frame_period = pipeline::frame_period(&config);
 
     loop {
         // I hope this doesn't lead to a catastrophic infinite loop failure
-        let client = create_client(&app_handle, &ntable_ip, &ntable_port).await;
+        shared.topics.lock().unwrap().clear();
 
-        let mut subscription: Subscription = match create_subscription(&client).await {
-            Ok(subscription) => {
-                app_handle
-                    .emit_all("telemetry_status", "connected")
-                    .expect("Failed to emit telemetry_connected event");
-                subscription
-            }
-            Err(_) => {
-                app_handle
-                    .emit_all("telemetry_status", "disconnected")
-                    .expect("Failed to emit telemetry_disconnected event");
-                continue;
+        let connected = match create_client(
+            status,
+            &shared.topics,
+            &config.target,
+            &config.connect_retry,
+        )
+        .await
+        {
+            Some(connected) => connected,
+            None => {
+                status.set(ConnectionState::Failed {
+                    reason: "Could not reach the robot on any address".to_string(),
+                });
+                return;
             }
         };
 
-        while let Some(mut message) = subscription.next().await {
-            process_message(&mut message);
-
-            let json_message = match to_string(&message) {
-                Ok(json) => json,
-                Err(_) => continue,
-            };
-
-            app_handle
-                .emit_all("telemetry_data", json_message.clone())
-                .expect("Failed to send telemetry message");
+        status.set(ConnectionState::Subscribing);
+        let live = ConnectionState::Live {
+            address: connected.candidate.to_string(),
+        };
 
-            app_handle
-                .emit_all("telemetry_status", "connected")
-                .expect("Failed to emit telemetry_connected event");
+        let subscriptions = match create_subscriptions(
+            &connected.client,
+            &subscription_sets,
+            status,
+            &config.subscribe_retry,
+        )
+        .await
+        {
+            Ok(subscriptions) => {
+                status.set(live.clone());
+                publisher.attach(connected.client.clone(), &config.publish_prefix);
+                subscriptions
+            }
+            Err(e) => {
+                status.set(ConnectionState::Failed {
+                    reason: format!("Failed to subscribe: {:?}", e),
+                });
+                continue;
+            }
+        };
+        let (_forwarders, mut messages) = merge_subscriptions(subscriptions);
+        pipeline.connect(live, Instant::now()).await;
 
-            check_triggers(
-                &app_handle,
-                &message.topic_name,
-                &message.data,
-                &previous_gpws,
-            );
+        let mut watchdog_interval = interval(WATCHDOG_INTERVAL);
+        let mut frame_interval = interval(frame_period);
+        frame_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
-            if message.topic_name == "gpws" {
-                previous_gpws = match message.data {
-                    network_tables::Value::Boolean(b) => b,
-                    _ => previous_gpws,
-                };
+        loop {
+            tokio::select! {
+                message = messages.recv() => match message {
+                    Some(message) => pipeline.process(message, Instant::now()).await,
+                    None => break,
+                },
+                _ = watchdog_interval.tick() => pipeline.check(Instant::now()),
+                _ = frame_interval.tick() => pipeline.flush(),
             }
-
-            tracing::debug!("{}", json_message);
         }
 
         tracing::debug!("disconnected");
-        app_handle
-            .emit_all("telemetry_status", "disconnected")
-            .expect("Failed to emit telemetry_disconnected event");
+        publisher.detach();
+        pipeline.disconnect();
+        status.set(ConnectionState::Failed {
+            reason: "Lost connection to the robot".to_string(),
+        });
     }
 }
-
-/// Strips the '/SmartDashboard/' prefix from NetworkTables topic names if present.
-///
-/// NetworkTables uses the '/SmartDashboard/' prefix to indicate that the topic
-/// was published to the  SmartDashboard. The SmartDashboard is a way for the robot
-/// to send data to any coprocessors, and it's published to the /SmartDashboard topic.
-///
-/// This function strips the '/SmartDashboard/' prefix from the topic name if
-/// it is present. This allows easier data processing from the frontend.
-fn process_message(message: &mut MessageData) {
-    message.topic_name = message
-        .topic_name
-        .trim_start_matches("/SmartDashboard/")
-        .to_string();
-}
@@ -0,0 +1,624 @@
+use std::time::Instant;
+
+use network_tables::v4::MessageData;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime, Wry};
+use tokio::time::Duration;
+
+use super::battery_monitor::BatteryMonitor;
+use super::chooser::ChooserEvent;
+use super::config::{RawMessages, TelemetryConfig};
+use super::drive_state::DriveStateTracker;
+use super::impact_detector::{ImpactDetector, ImpactEvent};
+use super::proto_schema::ProtoRegistry;
+use super::recorder::{Recorder, RecordingState};
+use super::shared::TelemetryShared;
+use super::speed_governor::{GovernorEvent, SpeedGovernor};
+use super::struct_schema::StructRegistry;
+use super::subscription_sets::display_name;
+use super::telemetry_state::TelemetryState;
+use super::telemetry_status::ConnectionState;
+use super::triggers::{TriggerEngine, TriggerEvent};
+use super::value_conversion::json_to_value;
+use super::watchdog::{FreshnessChange, FreshnessWatchdog};
+
+/// The fastest the telemetry state is emitted, whatever the configured frame
+/// rate.
+const MAX_UI_FRAME_RATE: f64 = 240.0;
+
+/// Returns how often the telemetry state should be emitted.
+pub fn frame_period(config: &TelemetryConfig) -> Duration {
+    Duration::from_secs_f64(1.0 / config.ui_frame_rate.clamp(1.0, MAX_UI_FRAME_RATE))
+}
+
+/// Turns received messages into the events the frontend listens to.
+///
+/// Struct and protobuf values are decoded with the schemas published under
+/// `/.schema`. The values the dashboard displays are collected into a
+/// `TelemetryState`, emitted as `telemetry_state` once per UI frame when it
+/// changed, and the last value of every topic is kept in `shared.values`.
+/// Every value also goes through the trigger rules, the battery monitor, the
+/// impact detector, the speed governor, the power tracker and the drive state,
+/// chooser, match context and match phase trackers, which emit their own
+/// events and keep their logs in [`TelemetryShared`]. A freshness watchdog
+/// emits `telemetry_stale` and `telemetry_fresh`, and a [`Recorder`], if any,
+/// writes every message to a `.wpilog` file before it is decoded.
+///
+/// The pipeline doesn't care where messages come from, so a live connection
+/// and a replayed recording behave exactly the same. The source calls
+/// [`TelemetryPipeline::connect`] and [`TelemetryPipeline::disconnect`] around
+/// each stream of messages, passes every message to
+/// [`TelemetryPipeline::process`], and calls [`TelemetryPipeline::check`] and
+/// [`TelemetryPipeline::flush`] periodically. Every call takes the time to
+/// process it at, which a replay advances with the recording rather than the
+/// wall clock so that hold times and recovery times come out as they did live.
+///
+/// While muted, messages update every tracker but no events are emitted, which
+/// lets a replay fast-forward to a point without replaying every alert on the
+/// way there.
+///
+/// The pipeline runs on any Tauri runtime, so tests can drive it with a mock
+/// app.
+pub struct TelemetryPipeline<R: Runtime = Wry> {
+    app_handle: AppHandle<R>,
+    config: TelemetryConfig,
+    shared: TelemetryShared<R>,
+    live: ConnectionState,
+    muted: bool,
+    recorder: Option<Recorder>,
+    drive_state: DriveStateTracker,
+    structs: StructRegistry,
+    protos: ProtoRegistry,
+    watchdog: FreshnessWatchdog,
+    triggers: TriggerEngine,
+    battery: BatteryMonitor,
+    impacts: ImpactDetector,
+    governor: SpeedGovernor,
+    state: TelemetryState,
+    state_changed: bool,
+    batch: Vec<MessageData>,
+    /// The name and payload of every event emitted, since Tauri 1 doesn't
+    /// pass events emitted to windows on to listeners in the backend.
+    #[cfg(test)]
+    emitted: std::sync::Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+impl<R: Runtime> TelemetryPipeline<R> {
+    /// Starts a new session, forgetting the history kept in `shared`.
+    ///
+    /// Messages are only recorded if a `recorder` is given.
+    pub fn new(
+        app_handle: AppHandle<R>,
+        config: TelemetryConfig,
+        shared: TelemetryShared<R>,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        shared.values.lock().unwrap().clear();
+        shared.impacts.lock().unwrap().clear();
+        shared.power.lock().unwrap().reset();
+
+        let now = Instant::now();
+        Self {
+            live: ConnectionState::Idle,
+            muted: false,
+            recorder,
+            drive_state: DriveStateTracker::default(),
+            structs: StructRegistry::default(),
+            protos: ProtoRegistry::default(),
+            watchdog: FreshnessWatchdog::new(config.watchdog.clone(), now),
+            triggers: TriggerEngine::new(config.triggers.clone()),
+            battery: BatteryMonitor::new(config.battery.clone(), now),
+            impacts: ImpactDetector::new(config.impacts.clone()),
+            governor: SpeedGovernor::new(config.speed_limits.clone()),
+            state: TelemetryState::default(),
+            state_changed: false,
+            batch: Vec::new(),
+            #[cfg(test)]
+            emitted: Default::default(),
+            app_handle,
+            config,
+            shared,
+        }
+    }
+
+    /// Starts a stream of messages, with `live` as the state to report while
+    /// data keeps arriving.
+    ///
+    /// Schemas and per-connection trackers are reset, while the session
+    /// history is kept.
+    pub async fn connect(&mut self, live: ConnectionState, now: Instant) {
+        self.live = live;
+        self.structs = StructRegistry::default();
+        self.protos = ProtoRegistry::default();
+        self.watchdog = FreshnessWatchdog::new(self.config.watchdog.clone(), now);
+        self.triggers = TriggerEngine::new(self.config.triggers.clone());
+        self.battery = BatteryMonitor::new(self.config.battery.clone(), now);
+        self.impacts = ImpactDetector::new(self.config.impacts.clone());
+        self.governor = SpeedGovernor::new(self.config.speed_limits.clone());
+        self.state = TelemetryState::default();
+        self.shared.choosers.lock().unwrap().clear();
+
+        self.set_speed_limit(self.governor.limit(), now).await;
+
+        let recording = self.recorder.as_mut().and_then(Recorder::connected);
+        self.report_recording(recording);
+    }
+
+    /// Ends the stream of messages started by [`TelemetryPipeline::connect`].
+    pub fn disconnect(&mut self) {
+        let recording = self.recorder.as_mut().and_then(Recorder::disconnected);
+        self.report_recording(recording);
+    }
+
+    /// Stops or resumes emitting events.
+    ///
+    /// Unmuting marks the telemetry state as changed, so the next frame brings
+    /// the dashboard up to date with everything processed while muted.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.state_changed |= self.muted && !muted;
+        self.muted = muted;
+    }
+
+    /// Checks the freshness deadlines and trigger hold times, and emits
+    /// `topics_changed` if the catalog changed.
+    pub fn check(&mut self, now: Instant) {
+        let changes = self.watchdog.check(now);
+        self.report_freshness(changes);
+        self.report_topics();
+        let events = self.triggers.check(now);
+        self.report_triggers(events);
+    }
+
+    /// Emits the messages batched and the telemetry state accumulated since the
+    /// last frame.
+    pub fn flush(&mut self) {
+        if !self.batch.is_empty() {
+            let batch = std::mem::take(&mut self.batch);
+            self.emit("telemetry_batch", batch);
+        }
+        if std::mem::take(&mut self.state_changed) {
+            self.emit("telemetry_state", self.state.clone());
+        }
+    }
+
+    /// Processes a message received on its full topic name.
+    pub async fn process(&mut self, mut message: MessageData, now: Instant) {
+        let shared = self.shared.clone();
+
+        let is_metadata =
+            shared
+                .topics
+                .lock()
+                .unwrap()
+                .record(&message.topic_name, &message.data, now);
+        if !is_metadata {
+            if let Some(recorder) = &mut self.recorder {
+                let type_name = shared.topics.lock().unwrap().type_name(&message.topic_name);
+                recorder.record(
+                    &message.topic_name,
+                    type_name.as_deref(),
+                    &message.data,
+                    message.timestamp,
+                );
+            }
+        }
+
+        if is_metadata
+            || self.structs.add_schema(&message.topic_name, &message.data)
+            || self.protos.add_schema(&message.topic_name, &message.data)
+        {
+            return;
+        }
+
+        self.decode_payload(&mut message);
+
+        let chooser_events = shared
+            .choosers
+            .lock()
+            .unwrap()
+            .update(&message.topic_name, &message.data);
+        self.report_choosers(chooser_events);
+
+        let match_context = {
+            let mut match_context = shared.match_context.lock().unwrap();
+            match_context
+                .update(&message.topic_name, &message.data)
+                .then(|| match_context.clone())
+        };
+        if let Some(match_context) = match_context {
+            let phase_change = shared
+                .match_phase
+                .lock()
+                .unwrap()
+                .update(&match_context.control, now);
+            if let Some(phase_change) = phase_change {
+                tracing::info!("{:?} -> {:?}", phase_change.from, phase_change.to);
+                let recording = self
+                    .recorder
+                    .as_mut()
+                    .and_then(|recorder| recorder.phase_changed(&phase_change));
+                self.report_recording(recording);
+                self.emit("match_phase_changed", phase_change);
+            }
+
+            self.emit("match_context", match_context);
+        }
+
+        message.topic_name = display_name(&self.config.subscriptions, &message.topic_name);
+        let topic_name = message.topic_name.as_str();
+        let data = &message.data;
+
+        let changes = self.watchdog.record(topic_name, now);
+        self.report_freshness(changes);
+
+        self.state_changed |= self.state.update(topic_name, data);
+        shared.values.lock().unwrap().record(topic_name, data, now);
+
+        let events = self.triggers.update(topic_name, data, now);
+        self.report_triggers(events);
+
+        if let Some(battery_state) = self.battery.update(topic_name, data, now) {
+            self.emit("battery_state", battery_state);
+        }
+
+        if let Some(change) = self.drive_state.update(topic_name, data) {
+            tracing::info!("{:?}", change);
+            self.emit("drive_state_changed", change);
+        }
+
+        let in_brownout = self.battery.in_brownout();
+        let power_event = shared
+            .power
+            .lock()
+            .unwrap()
+            .update(topic_name, data, in_brownout, now);
+        if let Some(power_event) = power_event {
+            tracing::info!(
+                "Recovered from {:.2} V after {} ms",
+                power_event.min_voltage,
+                power_event.recovery_ms
+            );
+            self.emit("power_event", power_event);
+        }
+
+        for event in self.impacts.update(topic_name, data, now) {
+            match event {
+                ImpactEvent::Impact(impact) => {
+                    tracing::info!("{:?} of {:.2} g", impact.kind, impact.magnitude);
+                    shared.impacts.lock().unwrap().push(impact.clone());
+                    self.emit("impact", impact);
+                }
+                ImpactEvent::Imminent(warning) => self.emit("collision_imminent", warning),
+            }
+        }
+
+        for event in self.governor.update(topic_name, data) {
+            match event {
+                GovernorEvent::Limit(limit) => self.set_speed_limit(limit, now).await,
+                GovernorEvent::Warning(warning) => self.emit("speed_warning", warning),
+            }
+        }
+
+        tracing::debug!("{}: {}", message.topic_name, message.data);
+
+        match self.config.raw_messages {
+            RawMessages::Off => {}
+            RawMessages::Each => self.emit("telemetry_data", message),
+            RawMessages::Batched => {
+                if !self.muted {
+                    self.batch.push(message);
+                }
+            }
+        }
+    }
+
+    /// Emits `event` to every window, unless the pipeline is muted.
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if self.muted {
+            return;
+        }
+
+        #[cfg(test)]
+        self.emitted.lock().unwrap().push((
+            event.to_string(),
+            serde_json::to_value(payload.clone()).unwrap(),
+        ));
+
+        if let Err(e) = self.app_handle.emit_all(event, payload) {
+            tracing::warn!("Failed to emit {} event: {}", event, e);
+        }
+    }
+
+    /// Takes the name and payload of every event emitted since the last call.
+    #[cfg(test)]
+    pub fn take_emitted(&self) -> Vec<(String, serde_json::Value)> {
+        std::mem::take(&mut *self.emitted.lock().unwrap())
+    }
+
+    /// Replaces the raw bytes of a struct- or protobuf-encoded message with the
+    /// decoded value.
+    ///
+    /// Struct messages that can't be decoded yet, e.g. because their schema
+    /// hasn't arrived, are left as they are. Protobuf messages that can't be
+    /// decoded are replaced by an object holding the `raw` bytes and the
+    /// `error` that occurred.
+    fn decode_payload(&self, message: &mut MessageData) {
+        let bytes = match &message.data {
+            network_tables::Value::Binary(bytes) => bytes,
+            _ => return,
+        };
+        let type_name = match self
+            .shared
+            .topics
+            .lock()
+            .unwrap()
+            .type_name(&message.topic_name)
+        {
+            Some(type_name) => type_name,
+            None => return,
+        };
+
+        match self.structs.decode(&type_name, bytes) {
+            Some(Ok(decoded)) => message.data = json_to_value(decoded),
+            Some(Err(e)) => tracing::debug!("Failed to decode {}: {}", message.topic_name, e),
+            None => match self.protos.decode(&type_name, bytes) {
+                Some(Ok(decoded)) => message.data = json_to_value(decoded),
+                Some(Err(e)) => {
+                    tracing::debug!("Failed to decode {}: {}", message.topic_name, e);
+                    message.data = network_tables::Value::Map(vec![
+                        ("raw".into(), network_tables::Value::Binary(bytes.clone())),
+                        ("error".into(), e.into()),
+                    ]);
+                }
+                None => {}
+            },
+        }
+    }
+
+    /// Sets the speed limit shown by the dashboard, and publishes it for the
+    /// robot and other dashboards to read.
+    async fn set_speed_limit(&mut self, limit: f64, now: Instant) {
+        self.state.speed_limit = limit;
+        self.state_changed = true;
+
+        let value = network_tables::Value::F64(limit);
+        self.shared
+            .values
+            .lock()
+            .unwrap()
+            .record("speed-limit", &value, now);
+
+        if let Err(e) = self.shared.publisher.publish("speed-limit", value).await {
+            tracing::debug!("Failed to publish speed limit: {}", e);
+        }
+    }
+
+    /// Emits the freshness changes noticed by the watchdog.
+    ///
+    /// Staleness of the link as a whole also moves the connection into
+    /// [`ConnectionState::Stale`], and back to the live state once data
+    /// resumes.
+    fn report_freshness(&self, changes: Vec<FreshnessChange>) {
+        for change in changes {
+            match &change {
+                FreshnessChange::Stale { topic, age_ms } => {
+                    tracing::debug!("stale: {:?} ({} ms)", topic, age_ms);
+                    if topic.is_none() {
+                        self.shared.status.set(ConnectionState::Stale);
+                    }
+
+                    self.emit("telemetry_stale", change);
+                }
+                FreshnessChange::Fresh { topic } => {
+                    if topic.is_none() {
+                        self.shared.status.set(self.live.clone());
+                    }
+
+                    self.emit("telemetry_fresh", change);
+                }
+            }
+        }
+    }
+
+    /// Emits `topics_changed` with the whole topic catalog if topics were
+    /// announced or unannounced since the last call.
+    fn report_topics(&self) {
+        let topics = {
+            let mut catalog = self.shared.topics.lock().unwrap();
+            catalog.take_changed().then(|| catalog.list())
+        };
+
+        if let Some(topics) = topics {
+            self.emit("topics_changed", topics);
+        }
+    }
+
+    /// Emits the change in recording state, if there was one.
+    fn report_recording(&self, state: Option<RecordingState>) {
+        if let Some(state) = state {
+            self.emit("recording_state", state);
+        }
+    }
+
+    /// Emits the events of the trigger rules that fired.
+    fn report_triggers(&self, events: Vec<TriggerEvent>) {
+        for event in events {
+            tracing::debug!("{}: {}", event.event, event.payload);
+            self.emit(&event.event, event.payload);
+        }
+    }
+
+    /// Emits the chooser changes noticed while processing a message.
+    fn report_choosers(&self, events: Vec<ChooserEvent>) {
+        for event in events {
+            match event {
+                ChooserEvent::Updated(chooser) => self.emit("chooser_updated", chooser),
+                ChooserEvent::Confirmed(confirmed) => {
+                    tracing::info!("{} confirmed {}", confirmed.path, confirmed.option);
+                    self.emit("chooser_confirmed", confirmed);
+                }
+            }
+        }
+    }
+}
+
+impl<R: Runtime> Drop for TelemetryPipeline<R> {
+    /// Finishes the recording in progress when the pipeline goes away without
+    /// disconnecting, like when the supervisor aborts its task.
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use network_tables::v4::Type;
+    use tauri::test::{mock_app, MockRuntime};
+
+    use super::*;
+
+    const TOPICS: u32 = 500;
+    const RATE: u32 = 50;
+    const SECONDS: u32 = 2;
+
+    fn pipeline(config: TelemetryConfig) -> TelemetryPipeline<MockRuntime> {
+        let app = mock_app();
+        let shared = TelemetryShared::new(app.handle());
+        TelemetryPipeline::new(app.handle(), config, shared, None)
+    }
+
+    fn live() -> ConnectionState {
+        ConnectionState::Live {
+            address: "mock".to_string(),
+        }
+    }
+
+    /// Takes the events emitted since the last call, keeping only those
+    /// called `event`.
+    fn take_emitted(
+        pipeline: &TelemetryPipeline<MockRuntime>,
+        event: &str,
+    ) -> Vec<serde_json::Value> {
+        pipeline
+            .take_emitted()
+            .into_iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload)
+            .collect()
+    }
+
+    /// Feeds the pipeline `TOPICS` topics at `RATE` Hz for `SECONDS` seconds of
+    /// robot time, flushing it once per UI frame, and checks that every
+    /// message reaches the frontend in at most one batch per frame.
+    #[tokio::test]
+    async fn batches_500_topics_at_50_hz_into_one_event_per_frame() {
+        let config = TelemetryConfig {
+            raw_messages: RawMessages::Batched,
+            ..TelemetryConfig::default()
+        };
+        let sample_period = Duration::from_secs(1) / RATE;
+        let frame_period = frame_period(&config);
+        let mut pipeline = pipeline(config);
+
+        let start = Instant::now();
+        pipeline.connect(live(), start).await;
+
+        let mut next_frame = start + frame_period;
+        let mut batched = 0;
+        let mut flush = |pipeline: &mut TelemetryPipeline<MockRuntime>| {
+            assert!(take_emitted(pipeline, "telemetry_batch").is_empty());
+            pipeline.flush();
+
+            let batches = take_emitted(pipeline, "telemetry_batch");
+            assert!(
+                batches.len() <= 1,
+                "emitted {} batches in a frame",
+                batches.len()
+            );
+            batched += batches
+                .iter()
+                .map(|batch| batch.as_array().unwrap().len())
+                .sum::<usize>();
+        };
+
+        for sample in 0..RATE * SECONDS {
+            let now = start + sample_period * sample;
+            while next_frame <= now {
+                flush(&mut pipeline);
+                next_frame += frame_period;
+            }
+
+            for topic in 0..TOPICS {
+                let message = MessageData {
+                    topic_name: format!("/SmartDashboard/bench/{}", topic),
+                    timestamp: sample * sample_period.as_micros() as u32,
+                    r#type: Type::Double,
+                    data: network_tables::Value::F64((sample + topic) as f64),
+                };
+                pipeline.process(message, now).await;
+            }
+        }
+        flush(&mut pipeline);
+
+        assert_eq!(batched, (TOPICS * RATE * SECONDS) as usize);
+    }
+
+    #[tokio::test]
+    async fn emits_no_raw_messages_by_default() {
+        let mut pipeline = pipeline(TelemetryConfig::default());
+        let now = Instant::now();
+        pipeline.connect(live(), now).await;
+
+        let message = MessageData {
+            topic_name: "/SmartDashboard/voltage".to_string(),
+            timestamp: 0,
+            r#type: Type::Double,
+            data: network_tables::Value::F64(12.5),
+        };
+        pipeline.process(message, now).await;
+        pipeline.flush();
+
+        let events: Vec<String> = pipeline
+            .take_emitted()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert!(events.contains(&"telemetry_state".to_string()));
+        assert!(!events
+            .iter()
+            .any(|event| event == "telemetry_data" || event == "telemetry_batch"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_raw_bytes_of_protobuf_payloads_that_cant_be_decoded() {
+        let mut pipeline = pipeline(TelemetryConfig {
+            raw_messages: RawMessages::Each,
+            ..TelemetryConfig::default()
+        });
+        let now = Instant::now();
+        pipeline.connect(live(), now).await;
+        pipeline
+            .shared
+            .topics
+            .lock()
+            .unwrap()
+            .announce_recorded("/SmartDashboard/pose", "proto:wpi.proto.Pose");
+
+        let message = MessageData {
+            topic_name: "/SmartDashboard/pose".to_string(),
+            timestamp: 0,
+            r#type: Type::Raw,
+            data: network_tables::Value::Binary(vec![1, 2, 3]),
+        };
+        pipeline.process(message, now).await;
+
+        let emitted = take_emitted(&pipeline, "telemetry_data");
+        assert_eq!(
+            emitted[0]["data"],
+            serde_json::json!({
+                "raw": [1, 2, 3],
+                "error": "No descriptor for wpi.proto.Pose",
+            })
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Instant;
+
+use network_tables::Value;
+use serde::Serialize;
+
+use super::epoch::epoch_ms;
+use super::telemetry_state::{Gear, Mode};
+
+/// A dip of the voltage into the brownout range, as emitted in the
+/// `power_event` event.
+///
+/// `timestamp` is when the dip started, in milliseconds since the Unix epoch,
+/// and `recovery_ms` is how long the voltage took to come back. `gear`,
+/// `acc_profile` and `speed` describe what the robot was doing when it
+/// started.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerEvent {
+    pub timestamp: u64,
+    pub recovery_ms: u64,
+    pub min_voltage: f64,
+    pub gear: Gear,
+    pub acc_profile: Mode,
+    pub speed: f64,
+}
+
+/// Statistics about the power events of a session, as returned by
+/// `get_power_stats`.
+///
+/// `min_voltage` is the lowest voltage seen at all this session, dip or not.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerStats {
+    pub count: usize,
+    pub min_voltage: Option<f64>,
+    pub total_recovery_ms: u64,
+    pub longest_recovery_ms: u64,
+    pub mean_recovery_ms: Option<f64>,
+    pub events: Vec<PowerEvent>,
+}
+
+struct Dip {
+    started: Instant,
+    event: PowerEvent,
+}
+
+/// Keeps a history of the brownout-range dips of a session.
+///
+/// Dips start and end as the [`BatteryMonitor`] moves into and out of the
+/// brownout range, so both always agree on what a brownout is.
+///
+/// [`BatteryMonitor`]: super::battery_monitor::BatteryMonitor
+#[derive(Default)]
+pub struct PowerTracker {
+    gear: Gear,
+    acc_profile: Mode,
+    speed: (f64, f64),
+    min_voltage: Option<f64>,
+    dip: Option<Dip>,
+    events: Vec<PowerEvent>,
+}
+
+impl PowerTracker {
+    /// Forgets the history and starts a new session.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Applies an update to the topic `topic_name`, which is the display name
+    /// the frontend knows it by, after the [`BatteryMonitor`] has seen it.
+    ///
+    /// `in_brownout` is whether the monitor now has the voltage in the
+    /// brownout range. Returns the dip that just ended, if any.
+    ///
+    /// [`BatteryMonitor`]: super::battery_monitor::BatteryMonitor
+    pub fn update(
+        &mut self,
+        topic_name: &str,
+        value: &Value,
+        in_brownout: bool,
+        now: Instant,
+    ) -> Option<PowerEvent> {
+        match topic_name {
+            "gear" => self.gear = value.as_str().and_then(Gear::from_name)?,
+            "acc-profile" => self.acc_profile = value.as_str().and_then(Mode::from_name)?,
+            "chassis-x-speed" => self.speed.0 = value.as_f64()?,
+            "chassis-y-speed" => self.speed.1 = value.as_f64()?,
+            "voltage" => return self.update_voltage(value.as_f64()?, in_brownout, now),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn update_voltage(
+        &mut self,
+        voltage: f64,
+        in_brownout: bool,
+        now: Instant,
+    ) -> Option<PowerEvent> {
+        // the robot publishes -999 and 0 before it has a reading
+        if voltage <= 0.0 {
+            return None;
+        }
+
+        self.min_voltage = Some(match self.min_voltage {
+            Some(min_voltage) => min_voltage.min(voltage),
+            None => voltage,
+        });
+
+        match &mut self.dip {
+            Some(dip) if !in_brownout => {
+                dip.event.recovery_ms = now.duration_since(dip.started).as_millis() as u64;
+                let event = dip.event.clone();
+                self.dip = None;
+                self.events.push(event.clone());
+                Some(event)
+            }
+            Some(dip) => {
+                dip.event.min_voltage = dip.event.min_voltage.min(voltage);
+                None
+            }
+            None if in_brownout => {
+                self.dip = Some(Dip {
+                    started: now,
+                    event: PowerEvent {
+                        timestamp: epoch_ms(),
+                        recovery_ms: 0,
+                        min_voltage: voltage,
+                        gear: self.gear,
+                        acc_profile: self.acc_profile,
+                        speed: self.speed.0.hypot(self.speed.1),
+                    },
+                });
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Summarizes the dips of the session. A dip that hasn't recovered yet
+    /// isn't included.
+    pub fn stats(&self) -> PowerStats {
+        let total_recovery_ms = self.events.iter().map(|event| event.recovery_ms).sum();
+
+        PowerStats {
+            count: self.events.len(),
+            min_voltage: self.min_voltage,
+            total_recovery_ms,
+            longest_recovery_ms: self
+                .events
+                .iter()
+                .map(|event| event.recovery_ms)
+                .max()
+                .unwrap_or_default(),
+            mean_recovery_ms: (!self.events.is_empty())
+                .then(|| total_recovery_ms as f64 / self.events.len() as f64),
+            events: self.events.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::battery_monitor::{BatteryConfig, BatteryMonitor};
+    use super::*;
+
+    #[test]
+    fn dips_follow_the_battery_monitors_brownouts() {
+        let start = Instant::now();
+        let mut monitor = BatteryMonitor::new(BatteryConfig::default(), start);
+        let mut tracker = PowerTracker::default();
+        let mut update = |topic: &str, value: Value, ms: u64| {
+            let now = start + Duration::from_millis(ms);
+            monitor.update(topic, &value, now);
+            tracker.update(topic, &value, monitor.in_brownout(), now)
+        };
+
+        update("gear", Value::from("drive"), 0);
+        update("chassis-x-speed", Value::F64(3.0), 0);
+        update("chassis-y-speed", Value::F64(4.0), 0);
+        assert_eq!(update("voltage", Value::F64(12.5), 0), None);
+        assert_eq!(update("voltage", Value::F64(6.5), 100), None);
+        assert_eq!(update("voltage", Value::F64(6.2), 150), None);
+        // still within the monitor's hysteresis
+        assert_eq!(update("voltage", Value::F64(6.9), 200), None);
+
+        let event = update("voltage", Value::F64(7.5), 340).unwrap();
+        assert_eq!(event.recovery_ms, 240);
+        assert_eq!(event.min_voltage, 6.2);
+        assert_eq!(event.gear, Gear::Drive);
+        assert_eq!(event.speed, 5.0);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.min_voltage, Some(6.2));
+        assert_eq!(stats.mean_recovery_ms, Some(240.0));
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage};
+use prost_types::FileDescriptorProto;
+
+use super::subscription_sets::SubscriptionSet;
+
+/// Prefix of the topics robot code publishes protobuf file descriptors to,
+/// e.g. `/.schema/proto:geometry2d.proto`.
+pub const PROTO_SCHEMA_PREFIX: &str = "/.schema/proto:";
+
+/// Prefix of the type string of protobuf-encoded topics, e.g.
+/// `proto:wpi.proto.ProtobufPose2d`.
+pub const PROTO_TYPE_PREFIX: &str = "proto:";
+
+/// Builds a descriptor pool out of the file descriptors published by the
+/// robot and decodes protobuf payloads with it.
+///
+/// A file can only be added to the pool once every file it imports is in the
+/// pool, and the robot publishes them in no particular order. Files are
+/// therefore kept aside until their dependencies arrive.
+#[derive(Default)]
+pub struct ProtoRegistry {
+    pool: DescriptorPool,
+    pending: HashMap<String, FileDescriptorProto>,
+}
+
+impl ProtoRegistry {
+    /// Adds the file descriptor published to the full topic name
+    /// `topic_name`.
+    ///
+    /// Returns whether the topic was a protobuf schema.
+    pub fn add_schema(&mut self, topic_name: &str, schema: &network_tables::Value) -> bool {
+        if !topic_name.starts_with(PROTO_SCHEMA_PREFIX) {
+            return false;
+        }
+
+        let bytes = match schema {
+            network_tables::Value::Binary(bytes) => bytes,
+            _ => return true,
+        };
+
+        match FileDescriptorProto::decode(bytes.as_slice()) {
+            Ok(file) => {
+                tracing::debug!("proto schema {}", file.name());
+                self.pending.insert(file.name().to_string(), file);
+                self.add_ready_files();
+            }
+            Err(e) => tracing::debug!("Invalid proto schema on {}: {}", topic_name, e),
+        }
+
+        true
+    }
+
+    /// Moves every pending file whose imports are all known into the pool.
+    fn add_ready_files(&mut self) {
+        loop {
+            let ready = self.pending.iter().find_map(|(name, file)| {
+                file.dependency
+                    .iter()
+                    .all(|dependency| self.pool.get_file_by_name(dependency).is_some())
+                    .then(|| name.clone())
+            });
+
+            let file = match ready.and_then(|name| self.pending.remove(&name)) {
+                Some(file) => file,
+                None => break,
+            };
+
+            let name = file.name().to_string();
+            if let Err(e) = self.pool.add_file_descriptor_proto(file) {
+                tracing::debug!("Failed to add proto schema {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Decodes a payload of the topic type `type_name`, like
+    /// `proto:wpi.proto.ProtobufPose2d`.
+    ///
+    /// Returns `None` if the type isn't a protobuf type.
+    pub fn decode(
+        &self,
+        type_name: &str,
+        bytes: &[u8],
+    ) -> Option<Result<serde_json::Value, String>> {
+        let name = type_name.strip_prefix(PROTO_TYPE_PREFIX)?;
+
+        let descriptor = match self.pool.get_message_by_name(name) {
+            Some(descriptor) => descriptor,
+            None => return Some(Err(format!("No descriptor for {}", name))),
+        };
+
+        Some(
+            DynamicMessage::decode(descriptor, bytes)
+                .map_err(|e| format!("Failed to decode {}: {}", name, e))
+                .and_then(|message| {
+                    serde_json::to_value(&message)
+                        .map_err(|e| format!("Failed to convert {} to JSON: {}", name, e))
+                }),
+        )
+    }
+}
+
+/// The subscription needed to receive protobuf file descriptors.
+pub fn schema_subscription_set() -> SubscriptionSet {
+    SubscriptionSet::prefixed("proto-schemas", PROTO_SCHEMA_PREFIX, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use network_tables::Value;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{DescriptorProto, FieldDescriptorProto};
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(
+        name: &str,
+        number: i32,
+        field_type: Type,
+        type_name: Option<&str>,
+    ) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(field_type as i32),
+            type_name: type_name.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn file(name: &str, dependencies: &[&str], message: DescriptorProto) -> Value {
+        let file = FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some("wpi.proto".to_string()),
+            dependency: dependencies.iter().map(|name| name.to_string()).collect(),
+            message_type: vec![message],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+        Value::Binary(file.encode_to_vec())
+    }
+
+    fn geometry_files() -> (Value, Value) {
+        let translation = file(
+            "translation.proto",
+            &[],
+            DescriptorProto {
+                name: Some("Translation".to_string()),
+                field: vec![
+                    field("x", 1, Type::Double, None),
+                    field("y", 2, Type::Double, None),
+                ],
+                ..Default::default()
+            },
+        );
+        let pose = file(
+            "pose.proto",
+            &["translation.proto"],
+            DescriptorProto {
+                name: Some("Pose".to_string()),
+                field: vec![
+                    field(
+                        "translation",
+                        1,
+                        Type::Message,
+                        Some(".wpi.proto.Translation"),
+                    ),
+                    field("rotation", 2, Type::Double, None),
+                ],
+                ..Default::default()
+            },
+        );
+        (translation, pose)
+    }
+
+    /// A `Pose` with a translation of (1.5, 0) and a rotation of 0.5.
+    fn pose_bytes() -> Vec<u8> {
+        let mut translation = vec![0x09];
+        translation.extend(1.5f64.to_le_bytes());
+
+        let mut bytes = vec![0x0a, translation.len() as u8];
+        bytes.extend(translation);
+        bytes.push(0x11);
+        bytes.extend(0.5f64.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn waits_for_imports_before_decoding() {
+        let (translation, pose) = geometry_files();
+        let mut registry = ProtoRegistry::default();
+
+        assert!(registry.add_schema("/.schema/proto:pose.proto", &pose));
+        assert_eq!(
+            registry.decode("proto:wpi.proto.Pose", &pose_bytes()),
+            Some(Err("No descriptor for wpi.proto.Pose".to_string()))
+        );
+
+        assert!(registry.add_schema("/.schema/proto:translation.proto", &translation));
+        assert_eq!(
+            registry.decode("proto:wpi.proto.Pose", &pose_bytes()),
+            Some(Ok(json!({ "translation": { "x": 1.5 }, "rotation": 0.5 })))
+        );
+        assert_eq!(registry.decode("struct:Pose2d", &pose_bytes()), None);
+        assert!(!registry.add_schema("/.schema/struct:Pose2d", &pose));
+    }
+
+    #[test]
+    fn reports_payloads_that_cant_be_decoded() {
+        let (translation, pose) = geometry_files();
+        let mut registry = ProtoRegistry::default();
+        registry.add_schema("/.schema/proto:translation.proto", &translation);
+        registry.add_schema("/.schema/proto:pose.proto", &pose);
+
+        let truncated = &pose_bytes()[..6];
+
+        match registry.decode("proto:wpi.proto.Pose", truncated) {
+            Some(Err(e)) => assert!(e.starts_with("Failed to decode wpi.proto.Pose"), "{}", e),
+            decoded => panic!("expected a decoding error, got {:?}", decoded),
+        }
+    }
+}
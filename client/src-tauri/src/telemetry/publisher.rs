@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use network_tables::v4::{Client, PublishedTopic, Type};
+use network_tables::Value;
+
+/// Everything that can go wrong when publishing a value.
+#[derive(Debug)]
+pub enum PublishError {
+    /// There is no connection to the robot to publish on.
+    NotConnected,
+    /// The value has no matching NetworkTables type, e.g. a map or a mixed
+    /// array.
+    UnsupportedValue(Value),
+    /// The topic was already announced with a different type.
+    TypeMismatch {
+        topic: String,
+        expected: Type,
+    },
+    NetworkTables(Box<network_tables::Error>),
+}
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublishError::NotConnected => write!(f, "Not connected to the robot"),
+            PublishError::UnsupportedValue(value) => {
+                write!(f, "Cannot publish {} to NetworkTables", value)
+            }
+            PublishError::TypeMismatch { topic, expected } => {
+                write!(f, "{} was already published as {:?}", topic, expected)
+            }
+            PublishError::NetworkTables(e) => write!(f, "NetworkTables error: {:?}", e),
+        }
+    }
+}
+
+/// The topics announced on one connection to the robot.
+struct PublisherSession {
+    client: Arc<Client>,
+    prefix: String,
+    topics: tokio::sync::Mutex<HashMap<String, (PublishedTopic, Type)>>,
+}
+
+/// Publishes values from the dashboard back to NetworkTables.
+///
+/// The publisher reuses the client created in `create_client`, so it is
+/// attached when a connection is made and detached when it is lost. Topics are
+/// announced the first time a value is published to them, under a prefix
+/// (`/Jankboard/` by default) so that dashboard-owned values are kept apart
+/// from the robot's own.
+#[derive(Default)]
+pub struct Publisher {
+    session: Mutex<Option<Arc<PublisherSession>>>,
+}
+
+impl Publisher {
+    /// Starts publishing on `client`, with relative topic names placed under
+    /// `prefix`.
+    pub fn attach(&self, client: Arc<Client>, prefix: &str) {
+        *self.session.lock().unwrap() = Some(Arc::new(PublisherSession {
+            client,
+            prefix: prefix.to_string(),
+            topics: tokio::sync::Mutex::new(HashMap::new()),
+        }));
+    }
+
+    /// Stops publishing, dropping every announced topic along with the client.
+    pub fn detach(&self) {
+        self.session.lock().unwrap().take();
+    }
+
+    /// Publishes `value` to `name` under the publish prefix, e.g.
+    /// `acc-profile` becomes `/Jankboard/acc-profile`.
+    pub async fn publish(&self, name: &str, value: Value) -> Result<(), PublishError> {
+        let session = self.session()?;
+        let topic_name = format!(
+            "{}/{}",
+            session.prefix.trim_end_matches('/'),
+            name.trim_start_matches('/')
+        );
+
+        publish_on(&session, topic_name, value).await
+    }
+
+    /// Publishes `value` to the absolute topic `topic_name`, ignoring the
+    /// publish prefix.
+    pub async fn publish_absolute(
+        &self,
+        topic_name: &str,
+        value: Value,
+    ) -> Result<(), PublishError> {
+        let session = self.session()?;
+        publish_on(&session, topic_name.to_string(), value).await
+    }
+
+    fn session(&self) -> Result<Arc<PublisherSession>, PublishError> {
+        self.session
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(PublishError::NotConnected)
+    }
+}
+
+/// Announces `topic_name` if needed and publishes `value` to it.
+async fn publish_on(
+    session: &PublisherSession,
+    topic_name: String,
+    value: Value,
+) -> Result<(), PublishError> {
+    let mut topics = session.topics.lock().await;
+
+    if !topics.contains_key(&topic_name) {
+        let topic_type = value_type(&value).ok_or(PublishError::UnsupportedValue(value.clone()))?;
+        let topic = session
+            .client
+            .publish_topic(&topic_name, topic_type, None)
+            .await
+            .map_err(|e| PublishError::NetworkTables(Box::new(e)))?;
+
+        tracing::debug!("Announced {} as {:?}", topic_name, topic_type);
+        topics.insert(topic_name.clone(), (topic, topic_type));
+    }
+
+    let (topic, topic_type) = &topics[&topic_name];
+    let value = coerce(value, topic_type).ok_or_else(|| PublishError::TypeMismatch {
+        topic: topic_name.clone(),
+        expected: *topic_type,
+    })?;
+
+    session
+        .client
+        .publish_value(topic, &value)
+        .await
+        .map_err(|e| PublishError::NetworkTables(Box::new(e)))
+}
+
+/// Picks the NetworkTables type a value should be announced as.
+fn value_type(value: &Value) -> Option<Type> {
+    match value {
+        Value::Boolean(_) => Some(Type::Boolean),
+        Value::Integer(_) => Some(Type::Int),
+        Value::F32(_) | Value::F64(_) => Some(Type::Double),
+        Value::String(_) => Some(Type::String),
+        Value::Binary(_) => Some(Type::Raw),
+        Value::Array(values) => {
+            if values.iter().all(Value::is_bool) {
+                Some(Type::BooleanArray)
+            } else if values.iter().all(Value::is_number) {
+                Some(Type::DoubleArray)
+            } else if values.iter().all(Value::is_str) {
+                Some(Type::StringArray)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Converts `value` to fit an already announced topic type, if possible.
+///
+/// Values coming from the frontend lose the distinction between integers and
+/// doubles, so numbers are converted between the two as needed.
+fn coerce(value: Value, topic_type: &Type) -> Option<Value> {
+    let element_type = match topic_type {
+        Type::Boolean if value.is_bool() => return Some(value),
+        Type::String if value.is_str() => return Some(value),
+        Type::Raw if value.is_bin() => return Some(value),
+        Type::Int => return value.as_i64().map(Value::from),
+        Type::Double | Type::Float => return value.as_f64().map(Value::from),
+        Type::BooleanArray => Type::Boolean,
+        Type::IntArray => Type::Int,
+        Type::DoubleArray | Type::FloatArray => Type::Double,
+        Type::StringArray => Type::String,
+        _ => return None,
+    };
+
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| coerce(value, &element_type))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        _ => None,
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use network_tables::Value;
+use serde::{Deserialize, Serialize};
+
+use super::match_phase::{MatchPhase, MatchPhaseChange};
+use super::wpilog::{log_type, WpilogWriter};
+
+/// Prefix AdvantageScope expects on entries that came from NetworkTables.
+pub const NT_ENTRY_PREFIX: &str = "NT:";
+
+/// When recordings start and stop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordMode {
+    /// Record for as long as the robot is connected.
+    #[default]
+    Connection,
+    /// Record from the start of each match until teleop ends.
+    Match,
+}
+
+/// Settings of the session [`Recorder`].
+///
+/// Recordings go to `directory`, or to `recordings` in the app data directory
+/// if it isn't set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecorderConfig {
+    pub enabled: bool,
+    pub mode: RecordMode,
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: RecordMode::default(),
+            directory: None,
+        }
+    }
+}
+
+/// The payload of the `recording_state` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingState {
+    pub recording: bool,
+    pub path: PathBuf,
+}
+
+struct Retained {
+    type_name: String,
+    value: Value,
+}
+
+struct Recording {
+    path: PathBuf,
+    writer: WpilogWriter<BufWriter<File>>,
+}
+
+/// Records every received topic to `.wpilog` files that open directly in
+/// AdvantageScope.
+///
+/// The latest value of every topic is retained while not recording, so that
+/// a recording started partway through a connection still contains values
+/// that are only sent once, like struct schemas.
+pub struct Recorder {
+    config: RecorderConfig,
+    directory: Option<PathBuf>,
+    recording: Option<Recording>,
+    retained: HashMap<String, Retained>,
+    timestamp: u64,
+}
+
+impl Recorder {
+    pub fn new(config: RecorderConfig, default_directory: Option<PathBuf>) -> Self {
+        Self {
+            directory: config.directory.clone().or(default_directory),
+            config,
+            recording: None,
+            retained: HashMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    /// Records a value received on the full topic name `topic_name`.
+    ///
+    /// `nt_timestamp` is the server timestamp of the value in microseconds.
+    /// NetworkTables sends it as 32 bits, so it is unwrapped here to keep the
+    /// log monotonic. Topics with an unknown type are skipped.
+    pub fn record(
+        &mut self,
+        topic_name: &str,
+        type_name: Option<&str>,
+        value: &Value,
+        nt_timestamp: u32,
+    ) {
+        let type_name = match type_name {
+            Some(type_name) => log_type(type_name),
+            None => return,
+        };
+
+        // the candidate closest to the latest timestamp, so that values
+        // arriving slightly out of order around a wrap stay in place
+        let wraps = self.timestamp >> 32;
+        let mut timestamp = (wraps << 32) | nt_timestamp as u64;
+        if timestamp + (1 << 31) < self.timestamp {
+            timestamp += 1 << 32;
+        } else if timestamp > self.timestamp + (1 << 31) && wraps > 0 {
+            timestamp -= 1 << 32;
+        }
+        self.timestamp = self.timestamp.max(timestamp);
+
+        let name = format!("{}{}", NT_ENTRY_PREFIX, topic_name);
+        if let Some(recording) = &mut self.recording {
+            if let Err(e) = recording.writer.append(&name, type_name, value, timestamp) {
+                tracing::warn!("Failed to write {}: {}", recording.path.display(), e);
+                self.recording = None;
+            }
+        }
+
+        self.retained.insert(
+            name,
+            Retained {
+                type_name: type_name.to_string(),
+                value: value.clone(),
+            },
+        );
+    }
+
+    /// Starts recording if recording by connection. Returns the new state.
+    ///
+    /// The server's clock is unrelated to the one of the previous connection,
+    /// so timestamps are unwrapped from scratch.
+    pub fn connected(&mut self) -> Option<RecordingState> {
+        self.timestamp = 0;
+
+        match self.config.mode {
+            RecordMode::Connection => self.start(),
+            RecordMode::Match => None,
+        }
+    }
+
+    /// Stops recording, forgetting the retained values of the connection.
+    /// Returns the new state.
+    pub fn disconnected(&mut self) -> Option<RecordingState> {
+        self.retained.clear();
+        let state = self.stop();
+        self.timestamp = 0;
+        state
+    }
+
+    /// Starts or stops recording if recording by match. Returns the new state.
+    pub fn phase_changed(&mut self, change: &MatchPhaseChange) -> Option<RecordingState> {
+        if self.config.mode != RecordMode::Match {
+            return None;
+        }
+
+        match (change.from, change.to) {
+            (_, MatchPhase::Autonomous) | (_, MatchPhase::Teleop) => self.start(),
+            (MatchPhase::Teleop, _) => self.stop(),
+            _ => None,
+        }
+    }
+
+    fn start(&mut self) -> Option<RecordingState> {
+        if !self.config.enabled || self.recording.is_some() {
+            return None;
+        }
+
+        let directory = self.directory.as_ref()?;
+        match self.create(directory) {
+            Ok(recording) => {
+                tracing::info!("Recording to {}", recording.path.display());
+                let path = recording.path.clone();
+                self.recording = Some(recording);
+                Some(RecordingState {
+                    recording: true,
+                    path,
+                })
+            }
+            Err(e) => {
+                tracing::warn!("Failed to start recording: {}", e);
+                None
+            }
+        }
+    }
+
+    fn create(&self, directory: &Path) -> io::Result<Recording> {
+        fs::create_dir_all(directory)?;
+
+        let name = format!("jankboard_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let (path, file) = create_unique(directory, &name)?;
+        let mut writer = WpilogWriter::new(BufWriter::new(file), "Jankboard")?;
+
+        // retained values are written as of now to keep the log in order
+        for (name, retained) in &self.retained {
+            writer.append(name, &retained.type_name, &retained.value, self.timestamp)?;
+        }
+
+        Ok(Recording { path, writer })
+    }
+
+    fn stop(&mut self) -> Option<RecordingState> {
+        let recording = self.recording.take()?;
+
+        if let Err(e) = recording.writer.finish(self.timestamp) {
+            tracing::warn!("Failed to finish {}: {}", recording.path.display(), e);
+        }
+        tracing::info!("Recorded {}", recording.path.display());
+
+        Some(RecordingState {
+            recording: false,
+            path: recording.path,
+        })
+    }
+}
+
+/// Creates `<name>.wpilog` in `directory`, or `<name>_2.wpilog` and so on if
+/// it already exists, so that recordings started within the same second never
+/// overwrite each other.
+fn create_unique(directory: &Path, name: &str) -> io::Result<(PathBuf, File)> {
+    let mut path = directory.join(format!("{}.wpilog", name));
+    let mut attempt = 1;
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                attempt += 1;
+                path = directory.join(format!("{}_{}.wpilog", name, attempt));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn unwraps_timestamps_per_connection() {
+        let config = RecorderConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let mut recorder = Recorder::new(config, None);
+        let record = |recorder: &mut Recorder, nt_timestamp| {
+            recorder.record("/voltage", Some("double"), &Value::F64(12.5), nt_timestamp);
+            recorder.timestamp
+        };
+
+        recorder.connected();
+        assert_eq!(record(&mut recorder, 0xffff_fff0), 0xffff_fff0);
+        assert_eq!(record(&mut recorder, 0x10), 0x1_0000_0010);
+        // a value arriving slightly out of order doesn't wrap again
+        assert_eq!(record(&mut recorder, 0xffff_fff8), 0x1_0000_0010);
+        assert_eq!(record(&mut recorder, 0x20), 0x1_0000_0020);
+
+        recorder.disconnected();
+        recorder.connected();
+        assert_eq!(record(&mut recorder, 0x30), 0x30);
+    }
+
+    #[test]
+    fn recordings_in_the_same_second_get_their_own_files() {
+        let directory = std::env::temp_dir().join(format!("jankboard_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let (first, mut file) = create_unique(&directory, "jankboard_20240316_103000").unwrap();
+        file.write_all(b"first").unwrap();
+        let (second, _) = create_unique(&directory, "jankboard_20240316_103000").unwrap();
+        let (third, _) = create_unique(&directory, "jankboard_20240316_103000").unwrap();
+
+        assert_eq!(first, directory.join("jankboard_20240316_103000.wpilog"));
+        assert_eq!(second, directory.join("jankboard_20240316_103000_2.wpilog"));
+        assert_eq!(third, directory.join("jankboard_20240316_103000_3.wpilog"));
+        assert_eq!(fs::read(&first).unwrap(), b"first");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
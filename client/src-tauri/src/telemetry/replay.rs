@@ -0,0 +1,595 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use network_tables::v4::{MessageData, Type};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime, Wry};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::{interval, sleep_until, Duration, MissedTickBehavior};
+
+use super::config::TelemetryConfig;
+use super::match_context::MatchContext;
+use super::match_phase::MatchPhaseTracker;
+use super::pipeline::{self, TelemetryPipeline};
+use super::recorder::NT_ENTRY_PREFIX;
+use super::shared::TelemetryShared;
+use super::telemetry_status::ConnectionState;
+use super::wpilog::{nt_type, read_wpilog, LogRecord, WpilogLog};
+use super::WATCHDOG_INTERVAL;
+
+/// A request to control a running replay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayCommand {
+    Play,
+    Pause,
+    /// Jumps to this many milliseconds into the recording.
+    Seek(u64),
+    /// Plays at this multiple of real time.
+    Speed(f64),
+}
+
+/// The payload of the `replay_status` event.
+///
+/// `position_ms` and `duration_ms` are measured from the first record of the
+/// recording.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayStatus {
+    pub path: PathBuf,
+    pub playing: bool,
+    pub speed: f64,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// A recording loaded for replay, with only the entries that are topics.
+pub struct Replay {
+    path: PathBuf,
+    topics: Vec<Option<(String, String)>>,
+    records: Vec<LogRecord>,
+    start: u64,
+    duration: u64,
+}
+
+impl Replay {
+    /// Reads the `.wpilog` file at `path`.
+    ///
+    /// NetworkTables entries are replayed on their topic names, while other
+    /// entries are only kept if they look like topics themselves, like the
+    /// `/DriverStation` entries the roboRIO logs.
+    pub async fn load(path: &Path) -> Result<Self, String> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let WpilogLog { entries, records } =
+            read_wpilog(&bytes).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let topics: Vec<Option<(String, String)>> = entries
+            .into_iter()
+            .map(|entry| {
+                let name = entry
+                    .name
+                    .strip_prefix(NT_ENTRY_PREFIX)
+                    .unwrap_or(&entry.name);
+                name.starts_with('/')
+                    .then(|| (name.to_string(), nt_type(&entry.type_name).to_string()))
+            })
+            .collect();
+        let records: Vec<LogRecord> = records
+            .into_iter()
+            .filter(|record| topics[record.entry].is_some())
+            .collect();
+
+        let (start, end) = match (records.first(), records.last()) {
+            (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+            _ => return Err(format!("{} has no topics to replay", path.display())),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            topics,
+            records,
+            start,
+            duration: end - start,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn message(&self, record: &LogRecord) -> MessageData {
+        let (topic_name, type_name) = self.topics[record.entry].clone().unwrap_or_default();
+
+        MessageData {
+            topic_name,
+            timestamp: record.timestamp as u32,
+            r#type: serde_json::from_value(serde_json::Value::String(type_name))
+                .unwrap_or(Type::Raw),
+            data: record.value.clone(),
+        }
+    }
+}
+
+/// Where a replay is and how it moves.
+///
+/// While playing, the position advances from `position` at `speed` times the
+/// time since `resumed`.
+#[derive(Clone, Copy)]
+struct Playhead {
+    position: u64,
+    resumed: Instant,
+    playing: bool,
+    speed: f64,
+}
+
+impl Playhead {
+    /// Returns the position in microseconds since the first record.
+    fn position(&self, now: Instant) -> u64 {
+        if !self.playing {
+            return self.position;
+        }
+
+        let elapsed = now.duration_since(self.resumed).as_secs_f64() * self.speed;
+        self.position + (elapsed * 1e6) as u64
+    }
+
+    /// Returns when playback reaches `position`.
+    fn reaches(&self, position: u64) -> Instant {
+        let remaining = position.saturating_sub(self.position) as f64 / 1e6 / self.speed;
+        self.resumed + Duration::from_secs_f64(remaining)
+    }
+
+    /// Moves to `position`, keeping on playing from there if playing.
+    fn set(&mut self, position: u64, now: Instant) {
+        self.position = position;
+        self.resumed = now;
+    }
+}
+
+/// The clock the pipeline of a replay runs on, which starts at `origin` and
+/// only advances with the playhead.
+#[derive(Clone, Copy)]
+pub struct ReplayClock {
+    origin: Instant,
+    playhead: Playhead,
+    duration: u64,
+}
+
+impl ReplayClock {
+    /// Returns the pipeline's time at the wall clock time `now`.
+    pub fn at(&self, now: Instant) -> Instant {
+        self.origin + Duration::from_micros(self.playhead.position(now).min(self.duration))
+    }
+}
+
+/// Feeds a recording through a [`TelemetryPipeline`] as if it was received
+/// live, so that the dashboard, triggers and sequences behave as they did.
+///
+/// The replay starts paused and is controlled through `commands`. Records are
+/// processed at the time they were recorded, scaled by the playback speed, and
+/// `replay_status` is emitted as the replay moves. Replays are never recorded.
+/// The function runs until `commands` is closed.
+pub async fn replay_session(
+    app_handle: AppHandle,
+    config: TelemetryConfig,
+    shared: TelemetryShared,
+    replay: Replay,
+    mut commands: UnboundedReceiver<ReplayCommand>,
+) {
+    let mut watchdog_interval = interval(WATCHDOG_INTERVAL);
+    let mut frame_interval = interval(pipeline::frame_period(&config));
+    frame_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut session = ReplaySession::start(app_handle, config, shared, replay).await;
+
+    loop {
+        let due = session.due();
+
+        tokio::select! {
+            _ = sleep_until(due.unwrap_or_else(Instant::now).into()), if due.is_some() => {
+                session.play(Instant::now()).await;
+            }
+            command = commands.recv() => match command {
+                Some(command) => session.control(command, Instant::now()).await,
+                None => break,
+            },
+            _ = watchdog_interval.tick() => session.check(Instant::now()),
+            _ = frame_interval.tick() => session.pipeline.flush(),
+        }
+    }
+
+    session.pipeline.disconnect();
+}
+
+/// A replay in progress: the recording, the pipeline it is fed through and
+/// where playback is.
+///
+/// `next` is the index of the first record that hasn't been processed yet.
+/// The pipeline runs on a clock of its own, which starts at `origin` and only
+/// advances with the playhead. It is kept in `shared.replay_clock` for the
+/// commands that need the current time.
+struct ReplaySession<R: Runtime = Wry> {
+    app_handle: AppHandle<R>,
+    config: TelemetryConfig,
+    shared: TelemetryShared<R>,
+    replay: Replay,
+    live: ConnectionState,
+    origin: Instant,
+    pipeline: TelemetryPipeline<R>,
+    next: usize,
+    playhead: Playhead,
+}
+
+impl<R: Runtime> ReplaySession<R> {
+    /// Announces the topics of `replay` and starts it, paused at its first
+    /// record.
+    async fn start(
+        app_handle: AppHandle<R>,
+        config: TelemetryConfig,
+        shared: TelemetryShared<R>,
+        replay: Replay,
+    ) -> Self {
+        let live = ConnectionState::Replaying {
+            path: replay.path.display().to_string(),
+        };
+
+        {
+            let mut topics = shared.topics.lock().unwrap();
+            topics.clear();
+            for (name, type_name) in replay.topics.iter().flatten() {
+                topics.announce_recorded(name, type_name);
+            }
+        }
+        shared.status.set(live.clone());
+
+        let origin = Instant::now();
+        let pipeline = start_pipeline(&app_handle, &config, &shared, &live, origin).await;
+        let session = Self {
+            app_handle,
+            config,
+            shared,
+            replay,
+            live,
+            origin,
+            pipeline,
+            next: 0,
+            playhead: Playhead {
+                position: 0,
+                resumed: origin,
+                playing: false,
+                speed: 1.0,
+            },
+        };
+
+        session.report_status(origin);
+        session
+    }
+
+    /// The pipeline's time at the position `position`.
+    fn clock(&self, position: u64) -> Instant {
+        self.origin + Duration::from_micros(position)
+    }
+
+    /// Returns when the next record is due, if playing.
+    fn due(&self) -> Option<Instant> {
+        self.replay
+            .records
+            .get(self.next)
+            .filter(|_| self.playhead.playing)
+            .map(|record| self.playhead.reaches(record.timestamp - self.replay.start))
+    }
+
+    /// Processes the records that are due at `now`.
+    async fn play(&mut self, now: Instant) {
+        let position = self.playhead.position(now).min(self.replay.duration);
+        self.process_until(position).await;
+        self.stop_at_end(now);
+    }
+
+    async fn control(&mut self, command: ReplayCommand, now: Instant) {
+        let position = self.playhead.position(now).min(self.replay.duration);
+        self.playhead.set(position, now);
+
+        match command {
+            ReplayCommand::Play => {
+                if self.next == self.replay.records.len() {
+                    // start over once the end was reached
+                    self.restart().await;
+                    self.playhead.set(0, now);
+                }
+                self.playhead.playing = true;
+            }
+            ReplayCommand::Pause => self.playhead.playing = false,
+            ReplayCommand::Speed(speed) => self.playhead.speed = speed,
+            ReplayCommand::Seek(position_ms) => {
+                let target = position_ms.saturating_mul(1000).min(self.replay.duration);
+                self.seek(target, now).await;
+                self.stop_at_end(now);
+            }
+        }
+
+        self.report_status(now);
+    }
+
+    /// Moves to `target`, keeping on playing from there if playing.
+    ///
+    /// Seeking backwards starts a new session, since the pipeline can't undo
+    /// what it has seen. The records up to `target` are then processed with
+    /// the pipeline muted, so no events fire on the way there.
+    async fn seek(&mut self, target: u64, now: Instant) {
+        if target < self.playhead.position {
+            self.restart().await;
+        }
+
+        self.pipeline.set_muted(true);
+        self.process_until(target).await;
+        self.pipeline.set_muted(false);
+        self.playhead.set(target, now);
+    }
+
+    /// Processes every record up to `position`.
+    async fn process_until(&mut self, position: u64) {
+        while let Some(record) = self.replay.records.get(self.next) {
+            let record_position = record.timestamp - self.replay.start;
+            if record_position > position {
+                break;
+            }
+
+            let message = self.replay.message(record);
+            let now = self.clock(record_position);
+            self.pipeline.process(message, now).await;
+            self.next += 1;
+        }
+    }
+
+    /// Stops at the end of the recording once every record was processed,
+    /// reporting that the replay finished.
+    fn stop_at_end(&mut self, now: Instant) {
+        if self.next == self.replay.records.len() && self.playhead.playing {
+            self.playhead.set(self.replay.duration, now);
+            self.playhead.playing = false;
+            self.report_status(now);
+        }
+    }
+
+    /// Forgets everything replayed so far and starts over from the first
+    /// record.
+    async fn restart(&mut self) {
+        self.pipeline = start_pipeline(
+            &self.app_handle,
+            &self.config,
+            &self.shared,
+            &self.live,
+            self.clock(0),
+        )
+        .await;
+        self.next = 0;
+    }
+
+    /// Checks the pipeline's deadlines, and reports the position while playing.
+    fn check(&mut self, now: Instant) {
+        let position = self.playhead.position(now).min(self.replay.duration);
+        self.pipeline.check(self.clock(position));
+        if self.playhead.playing {
+            self.report_status(now);
+        }
+    }
+
+    /// Stores the status of the replay in `shared.replay`, and its clock in
+    /// `shared.replay_clock`, and emits the status as `replay_status`.
+    fn report_status(&self, now: Instant) {
+        let status = ReplayStatus {
+            path: self.replay.path.clone(),
+            playing: self.playhead.playing,
+            speed: self.playhead.speed,
+            position_ms: self.playhead.position(now).min(self.replay.duration) / 1000,
+            duration_ms: self.replay.duration / 1000,
+        };
+
+        *self.shared.replay.lock().unwrap() = Some(status.clone());
+        *self.shared.replay_clock.lock().unwrap() = Some(ReplayClock {
+            origin: self.origin,
+            playhead: self.playhead,
+            duration: self.replay.duration,
+        });
+        if let Err(e) = self.app_handle.emit_all("replay_status", status) {
+            tracing::warn!("Failed to emit replay_status event: {}", e);
+        }
+    }
+}
+
+/// Forgets everything replayed so far and starts a new pipeline at `now`.
+async fn start_pipeline<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    config: &TelemetryConfig,
+    shared: &TelemetryShared<R>,
+    live: &ConnectionState,
+    now: Instant,
+) -> TelemetryPipeline<R> {
+    *shared.match_context.lock().unwrap() = MatchContext::default();
+    *shared.match_phase.lock().unwrap() = MatchPhaseTracker::new(now);
+
+    let mut pipeline =
+        TelemetryPipeline::new(app_handle.clone(), config.clone(), shared.clone(), None);
+    pipeline.connect(live.clone(), now).await;
+    pipeline
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::test::{mock_app, MockRuntime};
+
+    use super::super::watchdog::WatchdogConfig;
+    use super::*;
+
+    const SECOND: u64 = 1_000_000;
+
+    /// A recording of the voltage once a second from 12 V down to 9 V, with
+    /// the GPWS going off at 2 s.
+    fn replay() -> Replay {
+        let voltage = |second: u64, volts: f64| LogRecord {
+            entry: 0,
+            timestamp: 5 * SECOND + second * SECOND,
+            value: network_tables::Value::F64(volts),
+        };
+
+        Replay {
+            path: PathBuf::from("test.wpilog"),
+            topics: vec![
+                Some(("/SmartDashboard/voltage".to_string(), "double".to_string())),
+                Some(("/SmartDashboard/gpws".to_string(), "boolean".to_string())),
+                None,
+            ],
+            records: vec![
+                voltage(0, 12.0),
+                voltage(1, 11.0),
+                voltage(2, 10.0),
+                LogRecord {
+                    entry: 1,
+                    timestamp: 7 * SECOND,
+                    value: network_tables::Value::Boolean(true),
+                },
+                voltage(3, 9.0),
+            ],
+            start: 5 * SECOND,
+            duration: 3 * SECOND,
+        }
+    }
+
+    async fn session() -> ReplaySession<MockRuntime> {
+        let app = mock_app();
+        let shared = TelemetryShared::new(app.handle());
+        ReplaySession::start(app.handle(), TelemetryConfig::default(), shared, replay()).await
+    }
+
+    fn voltage(session: &ReplaySession<MockRuntime>) -> Option<f64> {
+        session
+            .shared
+            .values
+            .lock()
+            .unwrap()
+            .snapshot(Instant::now(), &WatchdogConfig::default(), false)
+            .into_iter()
+            .find(|value| value.topic == "voltage")
+            .and_then(|value| value.value.as_f64())
+    }
+
+    fn status(session: &ReplaySession<MockRuntime>) -> ReplayStatus {
+        session.shared.replay.lock().unwrap().clone().unwrap()
+    }
+
+    /// The pipeline's time at `now`, as the commands see it.
+    fn clock(session: &ReplaySession<MockRuntime>, now: Instant) -> Duration {
+        let clock = session.shared.replay_clock.lock().unwrap().unwrap();
+        clock.at(now) - session.origin
+    }
+
+    #[tokio::test]
+    async fn the_clock_follows_the_playhead() {
+        let mut session = session().await;
+        let start = session.origin;
+        let at = |seconds: f64| start + Duration::from_secs_f64(seconds);
+
+        // paused at the start
+        assert_eq!(clock(&session, at(5.0)), Duration::ZERO);
+
+        session.control(ReplayCommand::Play, at(1.0)).await;
+        assert_eq!(clock(&session, at(1.5)), Duration::from_millis(500));
+
+        session.control(ReplayCommand::Speed(2.0), at(1.5)).await;
+        assert_eq!(clock(&session, at(2.0)), Duration::from_millis(1500));
+
+        session.control(ReplayCommand::Pause, at(2.0)).await;
+        assert_eq!(clock(&session, at(10.0)), Duration::from_millis(1500));
+
+        session.control(ReplayCommand::Seek(250), at(10.0)).await;
+        assert_eq!(clock(&session, at(20.0)), Duration::from_millis(250));
+
+        // never past the end of the recording
+        session.control(ReplayCommand::Play, at(20.0)).await;
+        assert_eq!(clock(&session, at(40.0)), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn seeks_forwards_without_firing_events_on_the_way() {
+        let mut session = session().await;
+        session.pipeline.take_emitted();
+
+        session
+            .control(ReplayCommand::Seek(2500), Instant::now())
+            .await;
+
+        assert_eq!(voltage(&session), Some(10.0));
+        assert_eq!(session.next, 4);
+        assert!(session
+            .pipeline
+            .take_emitted()
+            .iter()
+            .all(|(event, _)| event != "telemetry_gpws"));
+
+        // the dashboard catches up on the next frame
+        session.pipeline.flush();
+        let emitted = session.pipeline.take_emitted();
+        assert!(emitted.iter().any(|(event, _)| event == "telemetry_state"));
+    }
+
+    #[tokio::test]
+    async fn seeks_backwards_by_starting_over() {
+        let mut session = session().await;
+        session
+            .control(ReplayCommand::Seek(2500), Instant::now())
+            .await;
+
+        session
+            .control(ReplayCommand::Seek(1000), Instant::now())
+            .await;
+
+        assert_eq!(voltage(&session), Some(11.0));
+        assert_eq!(session.next, 2);
+        assert_eq!(status(&session).position_ms, 1000);
+    }
+
+    #[tokio::test]
+    async fn reports_the_end_when_seeking_there_while_playing() {
+        let mut session = session().await;
+        let now = session.origin;
+        session.control(ReplayCommand::Play, now).await;
+
+        session.control(ReplayCommand::Seek(5000), now).await;
+
+        assert_eq!(voltage(&session), Some(9.0));
+        assert_eq!(session.due(), None);
+        assert_eq!(
+            status(&session),
+            ReplayStatus {
+                path: PathBuf::from("test.wpilog"),
+                playing: false,
+                speed: 1.0,
+                position_ms: 3000,
+                duration_ms: 3000,
+            }
+        );
+
+        // playing again starts over
+        session.control(ReplayCommand::Play, now).await;
+        assert_eq!(session.next, 0);
+        assert!(status(&session).playing);
+    }
+
+    #[tokio::test]
+    async fn plays_the_records_that_are_due() {
+        let mut session = session().await;
+        let start = session.origin;
+        session.control(ReplayCommand::Play, start).await;
+
+        assert_eq!(session.due(), Some(start));
+        session.play(start + Duration::from_millis(1500)).await;
+        assert_eq!(voltage(&session), Some(11.0));
+        assert_eq!(session.due(), Some(start + Duration::from_secs(2)));
+
+        session.play(start + Duration::from_secs(4)).await;
+        assert_eq!(voltage(&session), Some(9.0));
+        assert!(!status(&session).playing);
+        assert_eq!(status(&session).position_ms, 3000);
+    }
+}
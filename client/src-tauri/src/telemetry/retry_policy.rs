@@ -0,0 +1,87 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::time::Duration;
+
+/// Describes how long to wait between attempts of something that can fail,
+/// like connecting to the robot or creating a subscription.
+///
+/// The delay starts at `initial_delay_ms` and is multiplied by `multiplier`
+/// after every failed attempt, up to `max_delay_ms`. Each delay is then
+/// randomly stretched or shrunk by up to `jitter` (a fraction between 0 and
+/// 1), so that several dashboards don't all retry in lockstep. After
+/// `max_attempts` failures the caller gives up, or never if it is `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub initial_delay_ms: u64,
+    pub multiplier: f64,
+    pub max_delay_ms: u64,
+    pub jitter: f64,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 500,
+            multiplier: 2.0,
+            max_delay_ms: 5000,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Starts tracking attempts made under this policy.
+    pub fn backoff(&self) -> Backoff {
+        Backoff {
+            policy: self.clone(),
+            attempt: 0,
+        }
+    }
+}
+
+/// The progress of a sequence of attempts under a [`RetryPolicy`].
+pub struct Backoff {
+    policy: RetryPolicy,
+    attempt: u32,
+}
+
+impl Backoff {
+    /// The number of failed attempts so far.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// The maximum number of attempts, if there is one.
+    pub fn max_attempts(&self) -> Option<u32> {
+        self.policy.max_attempts
+    }
+
+    /// Records a failed attempt and returns how long to wait before the next
+    /// one, or `None` if the policy has run out of attempts.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        self.attempt += 1;
+
+        if let Some(max_attempts) = self.policy.max_attempts {
+            if self.attempt >= max_attempts {
+                return None;
+            }
+        }
+
+        let exponent = (self.attempt - 1) as i32;
+        let delay_ms = (self.policy.initial_delay_ms as f64
+            * self.policy.multiplier.max(1.0).powi(exponent))
+        .min(self.policy.max_delay_ms as f64);
+
+        let jitter = self.policy.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+
+        Some(Duration::from_millis((delay_ms * factor) as u64))
+    }
+}
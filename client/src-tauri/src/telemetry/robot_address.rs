@@ -0,0 +1,143 @@
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::lookup_host;
+
+/// The team number used when none is configured.
+pub const DEFAULT_TEAM_NUMBER: u16 = 1280;
+
+/// The port the NT4 server listens on.
+pub const DEFAULT_NTABLE_PORT: u16 = 5810;
+
+/// Static address the roboRIO takes when tethered over USB.
+const USB_ADDRESS: Ipv4Addr = Ipv4Addr::new(172, 22, 11, 2);
+
+/// A single place the robot's NetworkTables server might be reachable at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RobotAddress {
+    Ip(Ipv4Addr),
+    Hostname(String),
+}
+
+impl std::fmt::Display for RobotAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RobotAddress::Ip(ip) => write!(f, "{}", ip),
+            RobotAddress::Hostname(hostname) => write!(f, "{}", hostname),
+        }
+    }
+}
+
+/// Describes which robot the telemetry backend should connect to.
+///
+/// Instead of a single hardcoded IP, the target is a team number from which
+/// the standard FRC addresses are derived. An explicit address can also be
+/// given, in which case it is tried before any of the derived ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryTarget {
+    pub team_number: u16,
+    pub port: u16,
+    pub address: Option<String>,
+}
+
+impl Default for TelemetryTarget {
+    fn default() -> Self {
+        Self {
+            team_number: DEFAULT_TEAM_NUMBER,
+            port: DEFAULT_NTABLE_PORT,
+            address: None,
+        }
+    }
+}
+
+impl TelemetryTarget {
+    /// Overrides fields of the target from the environment, if set.
+    ///
+    /// * `JANKBOARD_TEAM` - the team number to derive addresses from
+    /// * `JANKBOARD_NT_PORT` - the NetworkTables server port
+    /// * `JANKBOARD_ROBOT_ADDRESS` - an explicit IP or hostname to try first
+    pub fn apply_env(&mut self) {
+        if let Some(team_number) = std::env::var("JANKBOARD_TEAM")
+            .ok()
+            .and_then(|team| team.trim().parse().ok())
+        {
+            self.team_number = team_number;
+        }
+
+        if let Some(port) = std::env::var("JANKBOARD_NT_PORT")
+            .ok()
+            .and_then(|port| port.trim().parse().ok())
+        {
+            self.port = port;
+        }
+
+        if let Some(address) = std::env::var("JANKBOARD_ROBOT_ADDRESS")
+            .ok()
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+        {
+            self.address = Some(address);
+        }
+    }
+
+    /// Returns every address the robot might be reachable at, in the order
+    /// they should be tried.
+    ///
+    /// For team 1280 this is:
+    ///
+    /// * the explicit address, if one was configured
+    /// * `10.12.80.2` - the robot radio network
+    /// * `roborio-1280-frc.local` - mDNS
+    /// * `172.22.11.2` - USB tether
+    /// * `127.0.0.1` - simulation
+    pub fn candidates(&self) -> Vec<RobotAddress> {
+        let mut candidates = Vec::new();
+
+        if let Some(address) = &self.address {
+            candidates.push(match address.parse::<Ipv4Addr>() {
+                Ok(ip) => RobotAddress::Ip(ip),
+                Err(_) => RobotAddress::Hostname(address.clone()),
+            });
+        }
+
+        candidates.push(RobotAddress::Ip(team_ip(self.team_number)));
+        candidates.push(RobotAddress::Hostname(format!(
+            "roborio-{}-frc.local",
+            self.team_number
+        )));
+        candidates.push(RobotAddress::Ip(USB_ADDRESS));
+        candidates.push(RobotAddress::Ip(Ipv4Addr::LOCALHOST));
+
+        candidates.dedup();
+        candidates
+    }
+}
+
+/// Computes the `10.TE.AM.2` address of the roboRIO for a team number.
+pub fn team_ip(team_number: u16) -> Ipv4Addr {
+    Ipv4Addr::new(10, (team_number / 100) as u8, (team_number % 100) as u8, 2)
+}
+
+/// Resolves a candidate address into socket addresses.
+///
+/// Hostnames are looked up every time this is called, since mDNS names only
+/// start resolving once the robot is on the network. A hostname that fails to
+/// resolve yields no addresses.
+pub async fn resolve(address: &RobotAddress, port: u16) -> Vec<SocketAddrV4> {
+    match address {
+        RobotAddress::Ip(ip) => vec![SocketAddrV4::new(*ip, port)],
+        RobotAddress::Hostname(hostname) => match lookup_host((hostname.as_str(), port)).await {
+            Ok(addresses) => addresses
+                .filter_map(|address| match address {
+                    SocketAddr::V4(address) => Some(address),
+                    SocketAddr::V6(_) => None,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::debug!("Failed to resolve {}: {}", hostname, e);
+                Vec::new()
+            }
+        },
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tauri::{AppHandle, Runtime, Wry};
+
+use super::chooser::ChooserRegistry;
+use super::impact_detector::Impact;
+use super::match_context::MatchContext;
+use super::match_phase::MatchPhaseTracker;
+use super::power_tracker::PowerTracker;
+use super::publisher::Publisher;
+use super::replay::{ReplayClock, ReplayStatus};
+use super::telemetry_status::TelemetryStatus;
+use super::topic_catalog::TopicCatalog;
+use super::value_cache::ValueCache;
+
+/// State shared between the telemetry task and the commands that query or
+/// control it.
+///
+/// Everything in here outlives a single telemetry task, so a restart keeps
+/// the same instances and commands never hold on to stale ones.
+pub struct TelemetryShared<R: Runtime = Wry> {
+    pub status: Arc<TelemetryStatus<R>>,
+    pub publisher: Arc<Publisher>,
+    pub choosers: Arc<Mutex<ChooserRegistry>>,
+    pub match_context: Arc<Mutex<MatchContext>>,
+    pub topics: Arc<Mutex<TopicCatalog>>,
+    pub values: Arc<Mutex<ValueCache>>,
+    pub impacts: Arc<Mutex<Vec<Impact>>>,
+    pub power: Arc<Mutex<PowerTracker>>,
+    pub match_phase: Arc<Mutex<MatchPhaseTracker>>,
+    pub replay: Arc<Mutex<Option<ReplayStatus>>>,
+    pub replay_clock: Arc<Mutex<Option<ReplayClock>>>,
+}
+
+impl<R: Runtime> TelemetryShared<R> {
+    pub fn new(app_handle: AppHandle<R>) -> Self {
+        Self {
+            status: Arc::new(TelemetryStatus::new(app_handle)),
+            publisher: Arc::new(Publisher::default()),
+            choosers: Arc::new(Mutex::new(ChooserRegistry::default())),
+            match_context: Arc::new(Mutex::new(MatchContext::default())),
+            topics: Arc::new(Mutex::new(TopicCatalog::default())),
+            values: Arc::new(Mutex::new(ValueCache::default())),
+            impacts: Arc::new(Mutex::new(Vec::new())),
+            power: Arc::new(Mutex::new(PowerTracker::default())),
+            match_phase: Arc::new(Mutex::new(MatchPhaseTracker::new(Instant::now()))),
+            replay: Arc::new(Mutex::new(None)),
+            replay_clock: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the current time as the telemetry pipeline sees it, which
+    /// follows the playhead while replaying.
+    pub fn now(&self) -> Instant {
+        let now = Instant::now();
+        self.replay_clock
+            .lock()
+            .unwrap()
+            .map_or(now, |clock| clock.at(now))
+    }
+}
+
+// not derived, since that would require the runtime to be `Clone`
+impl<R: Runtime> Clone for TelemetryShared<R> {
+    fn clone(&self) -> Self {
+        Self {
+            status: self.status.clone(),
+            publisher: self.publisher.clone(),
+            choosers: self.choosers.clone(),
+            match_context: self.match_context.clone(),
+            topics: self.topics.clone(),
+            values: self.values.clone(),
+            impacts: self.impacts.clone(),
+            power: self.power.clone(),
+            match_phase: self.match_phase.clone(),
+            replay: self.replay.clone(),
+            replay_clock: self.replay_clock.clone(),
+        }
+    }
+}
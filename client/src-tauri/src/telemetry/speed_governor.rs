@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use network_tables::Value;
+use serde::{Deserialize, Serialize};
+
+use super::telemetry_state::{Gear, Mode};
+
+/// Speed limits used by the [`SpeedGovernor`], in the same units as the
+/// chassis speed topics.
+///
+/// The limit is the lowest of `default_limit`, the limit of the current gear
+/// in `gears` and the limit of the current acceleration profile in
+/// `profiles`. The robot is approaching the limit above `approach_ratio` of
+/// it, and a warning is only cleared once the speed is `hysteresis` below the
+/// speed that raised it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedLimitConfig {
+    pub default_limit: f64,
+    pub gears: HashMap<Gear, f64>,
+    pub profiles: HashMap<Mode, f64>,
+    pub approach_ratio: f64,
+    pub hysteresis: f64,
+}
+
+impl Default for SpeedLimitConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: 5.0,
+            gears: HashMap::from([(Gear::Reverse, 2.0), (Gear::Low, 2.5)]),
+            profiles: HashMap::from([(Mode::Chill, 3.0)]),
+            approach_ratio: 0.9,
+            hysteresis: 0.2,
+        }
+    }
+}
+
+/// How the robot's speed compares to the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedZone {
+    #[default]
+    Under,
+    Approaching,
+    Over,
+}
+
+/// The payload of the `speed_warning` event.
+///
+/// `from` is the zone the speed was in before, which tells speeding up into
+/// `Approaching` apart from slowing down into it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpeedWarning {
+    pub zone: SpeedZone,
+    pub from: SpeedZone,
+    pub speed: f64,
+    pub limit: f64,
+}
+
+/// A change noticed by the [`SpeedGovernor`].
+pub enum GovernorEvent {
+    /// The limit changed, e.g. because the robot shifted gears.
+    Limit(f64),
+    /// The speed moved into a different zone.
+    Warning(SpeedWarning),
+}
+
+/// Compares the robot's speed to a limit that depends on its gear and
+/// acceleration profile.
+pub struct SpeedGovernor {
+    config: SpeedLimitConfig,
+    speed: (f64, f64),
+    gear: Gear,
+    profile: Mode,
+    limit: f64,
+    zone: SpeedZone,
+}
+
+impl SpeedGovernor {
+    pub fn new(config: SpeedLimitConfig) -> Self {
+        let mut governor = Self {
+            config,
+            speed: (0.0, 0.0),
+            gear: Gear::default(),
+            profile: Mode::default(),
+            limit: 0.0,
+            zone: SpeedZone::default(),
+        };
+        governor.limit = governor.compute_limit();
+        governor
+    }
+
+    /// Returns the current speed limit.
+    pub fn limit(&self) -> f64 {
+        self.limit
+    }
+
+    /// Applies an update to the topic `topic_name`, which is the display name
+    /// the frontend knows it by.
+    pub fn update(&mut self, topic_name: &str, value: &Value) -> Vec<GovernorEvent> {
+        match topic_name {
+            "chassis-x-speed" | "chassis-y-speed" => {
+                let number = match value.as_f64() {
+                    Some(number) => number,
+                    None => return Vec::new(),
+                };
+                if topic_name == "chassis-x-speed" {
+                    self.speed.0 = number;
+                } else {
+                    self.speed.1 = number;
+                }
+            }
+            "gear" => match value.as_str().and_then(Gear::from_name) {
+                Some(gear) => self.gear = gear,
+                None => return Vec::new(),
+            },
+            "acc-profile" => match value.as_str().and_then(Mode::from_name) {
+                Some(profile) => self.profile = profile,
+                None => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        }
+
+        let mut events = Vec::new();
+
+        let limit = self.compute_limit();
+        if limit != self.limit {
+            self.limit = limit;
+            events.push(GovernorEvent::Limit(limit));
+        }
+
+        let speed = self.speed.0.hypot(self.speed.1);
+        let zone = self.next_zone(speed);
+        if zone != self.zone {
+            let from = std::mem::replace(&mut self.zone, zone);
+            events.push(GovernorEvent::Warning(SpeedWarning {
+                zone,
+                from,
+                speed,
+                limit: self.limit,
+            }));
+        }
+
+        events
+    }
+
+    fn compute_limit(&self) -> f64 {
+        [
+            self.config.gears.get(&self.gear),
+            self.config.profiles.get(&self.profile),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(self.config.default_limit, |limit, other| limit.min(*other))
+    }
+
+    /// Decides the zone of `speed`, only leaving the current zone once the
+    /// speed is the hysteresis below the threshold that entered it.
+    fn next_zone(&self, speed: f64) -> SpeedZone {
+        let over = self.limit;
+        let approaching = self.limit * self.config.approach_ratio;
+        let hysteresis = self.config.hysteresis;
+
+        match self.zone {
+            SpeedZone::Over if speed > over - hysteresis => SpeedZone::Over,
+            SpeedZone::Over | SpeedZone::Approaching
+                if speed > approaching - hysteresis && speed <= over =>
+            {
+                SpeedZone::Approaching
+            }
+            _ if speed > over => SpeedZone::Over,
+            _ if speed > approaching => SpeedZone::Approaching,
+            _ => SpeedZone::Under,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warnings(events: Vec<GovernorEvent>) -> Vec<(SpeedZone, SpeedZone)> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                GovernorEvent::Warning(warning) => Some((warning.from, warning.zone)),
+                GovernorEvent::Limit(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn warnings_tell_where_the_speed_came_from() {
+        let mut governor = SpeedGovernor::new(SpeedLimitConfig::default());
+        let mut drive =
+            |speed: f64| warnings(governor.update("chassis-x-speed", &Value::F64(speed)));
+
+        // the chill profile limits the speed to 3
+        assert_eq!(drive(2.8), [(SpeedZone::Under, SpeedZone::Approaching)]);
+        assert_eq!(drive(3.5), [(SpeedZone::Approaching, SpeedZone::Over)]);
+        assert_eq!(drive(2.75), [(SpeedZone::Over, SpeedZone::Approaching)]);
+        assert_eq!(drive(2.0), [(SpeedZone::Approaching, SpeedZone::Under)]);
+    }
+}
@@ -0,0 +1,702 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{json, Map};
+
+use super::subscription_sets::SubscriptionSet;
+
+/// Prefix of the topics robot code publishes struct schemas to, e.g.
+/// `/.schema/struct:Pose2d`.
+pub const STRUCT_SCHEMA_PREFIX: &str = "/.schema/struct:";
+
+/// Prefix of the type string of struct-encoded topics, e.g. `struct:Pose2d`
+/// or `struct:SwerveModuleState[]`.
+pub const STRUCT_TYPE_PREFIX: &str = "struct:";
+
+/// Everything that can go wrong when decoding a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructError {
+    /// No schema has been received for the struct yet.
+    UnknownStruct(String),
+    /// The schema text could not be parsed.
+    InvalidSchema { name: String, reason: String },
+    /// The payload length doesn't match the size of the struct.
+    WrongSize {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// The struct contains itself, directly or through other structs.
+    Recursive(String),
+}
+
+impl fmt::Display for StructError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructError::UnknownStruct(name) => write!(f, "No schema for struct {}", name),
+            StructError::InvalidSchema { name, reason } => {
+                write!(f, "Invalid schema for struct {}: {}", name, reason)
+            }
+            StructError::WrongSize {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Expected {} bytes for struct {}, got {}",
+                expected, name, actual
+            ),
+            StructError::Recursive(name) => write!(f, "Struct {} contains itself", name),
+        }
+    }
+}
+
+/// The primitive types a struct field can have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Primitive {
+    Bool,
+    Char,
+    Int(usize),
+    Uint(usize),
+    Float,
+    Double,
+}
+
+impl Primitive {
+    fn parse(type_name: &str) -> Option<Self> {
+        Some(match type_name {
+            "bool" => Primitive::Bool,
+            "char" => Primitive::Char,
+            "int8" => Primitive::Int(1),
+            "int16" => Primitive::Int(2),
+            "int32" => Primitive::Int(4),
+            "int64" => Primitive::Int(8),
+            "uint8" => Primitive::Uint(1),
+            "uint16" => Primitive::Uint(2),
+            "uint32" => Primitive::Uint(4),
+            "uint64" => Primitive::Uint(8),
+            "float" | "float32" => Primitive::Float,
+            "double" | "float64" => Primitive::Double,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Primitive::Bool | Primitive::Char => 1,
+            Primitive::Int(size) | Primitive::Uint(size) => size,
+            Primitive::Float => 4,
+            Primitive::Double => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldType {
+    Primitive(Primitive),
+    Struct(String),
+}
+
+/// A single field declaration of a struct schema.
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    name: String,
+    field_type: FieldType,
+    array_len: Option<usize>,
+    bit_width: Option<u32>,
+    enum_values: Option<HashMap<i64, String>>,
+}
+
+/// Parses a schema like `double x;double y;Rotation2d rotation`.
+fn parse_schema(schema: &str) -> Result<Vec<Field>, String> {
+    schema
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| !declaration.is_empty())
+        .map(parse_declaration)
+        .collect()
+}
+
+/// Parses a single declaration, like `int8 value:4`, `double arr[4]` or
+/// `enum {a=1, b=2} int8 value`.
+fn parse_declaration(declaration: &str) -> Result<Field, String> {
+    let mut rest = declaration;
+    let mut enum_values = None;
+
+    if let Some(after_enum) = rest
+        .strip_prefix("enum")
+        .filter(|after_enum| after_enum.trim_start().starts_with('{'))
+    {
+        let open = after_enum
+            .find('{')
+            .ok_or_else(|| format!("Missing enum values in `{}`", declaration))?;
+        let close = after_enum
+            .find('}')
+            .ok_or_else(|| format!("Unclosed enum values in `{}`", declaration))?;
+
+        let mut values = HashMap::new();
+        for value in after_enum[open + 1..close].split(',') {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            let (name, number) = value
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid enum value `{}`", value))?;
+            let number = number
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid enum value `{}`", value))?;
+            values.insert(number, name.trim().to_string());
+        }
+
+        enum_values = Some(values);
+        rest = after_enum[close + 1..].trim();
+    }
+
+    let (type_name, rest) = rest
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("Missing field name in `{}`", declaration))?;
+    let rest: String = rest.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let (name, array_len, bit_width) = if let Some((name, len)) = rest.split_once('[') {
+        let len = len
+            .strip_suffix(']')
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(|| format!("Invalid array length in `{}`", declaration))?;
+        (name.to_string(), Some(len), None)
+    } else if let Some((name, bits)) = rest.split_once(':') {
+        let bits = bits
+            .parse()
+            .map_err(|_| format!("Invalid bit width in `{}`", declaration))?;
+        (name.to_string(), None, Some(bits))
+    } else {
+        (rest, None, None)
+    };
+
+    let field_type = match Primitive::parse(type_name) {
+        Some(primitive) => FieldType::Primitive(primitive),
+        None => FieldType::Struct(type_name.to_string()),
+    };
+
+    if let Some(width) = bit_width {
+        let max_width = match field_type {
+            FieldType::Primitive(Primitive::Bool) => 1,
+            FieldType::Primitive(primitive @ (Primitive::Int(_) | Primitive::Uint(_))) => {
+                primitive.size() as u32 * 8
+            }
+            _ => {
+                return Err(format!(
+                    "Bit-field on a non-integer type in `{}`",
+                    declaration
+                ))
+            }
+        };
+
+        if width == 0 || width > max_width {
+            return Err(format!("Invalid bit width in `{}`", declaration));
+        }
+    }
+
+    Ok(Field {
+        name,
+        field_type,
+        array_len,
+        bit_width,
+        enum_values,
+    })
+}
+
+/// Keeps the struct schemas published by the robot and decodes struct
+/// payloads with them.
+///
+/// Schemas are published as topics under `/.schema/struct:`. Payloads are
+/// packed little-endian values in declaration order, with nested structs
+/// inlined and bit-fields packed into shared integers.
+#[derive(Default)]
+pub struct StructRegistry {
+    schemas: HashMap<String, Result<Vec<Field>, String>>,
+}
+
+impl StructRegistry {
+    /// Adds the schema published to the full topic name `topic_name`.
+    ///
+    /// Returns whether the topic was a struct schema.
+    pub fn add_schema(&mut self, topic_name: &str, schema: &network_tables::Value) -> bool {
+        let name = match topic_name.strip_prefix(STRUCT_SCHEMA_PREFIX) {
+            Some(name) => name,
+            None => return false,
+        };
+
+        let schema = match schema {
+            network_tables::Value::Binary(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            network_tables::Value::String(schema) => {
+                schema.as_str().unwrap_or_default().to_string()
+            }
+            _ => return true,
+        };
+
+        tracing::debug!("struct schema {}: {}", name, schema);
+        self.schemas.insert(name.to_string(), parse_schema(&schema));
+        true
+    }
+
+    /// Decodes a payload of the topic type `type_name`, like `struct:Pose2d`
+    /// or `struct:SwerveModuleState[]`.
+    ///
+    /// Returns `None` if the type isn't a struct type.
+    pub fn decode(
+        &self,
+        type_name: &str,
+        bytes: &[u8],
+    ) -> Option<Result<serde_json::Value, StructError>> {
+        let name = type_name.strip_prefix(STRUCT_TYPE_PREFIX)?;
+
+        Some(match name.strip_suffix("[]") {
+            Some(name) => self.decode_array(name, bytes),
+            None => self.decode_exact(name, bytes),
+        })
+    }
+
+    fn decode_exact(&self, name: &str, bytes: &[u8]) -> Result<serde_json::Value, StructError> {
+        let size = self.size_of(name)?;
+        if bytes.len() != size {
+            return Err(StructError::WrongSize {
+                name: name.to_string(),
+                expected: size,
+                actual: bytes.len(),
+            });
+        }
+
+        self.decode_struct(name, bytes)
+    }
+
+    fn decode_array(&self, name: &str, bytes: &[u8]) -> Result<serde_json::Value, StructError> {
+        let size = self.size_of(name)?;
+        if size == 0 || bytes.len() % size != 0 {
+            return Err(StructError::WrongSize {
+                name: format!("{}[]", name),
+                expected: size,
+                actual: bytes.len(),
+            });
+        }
+
+        bytes
+            .chunks(size)
+            .map(|chunk| self.decode_struct(name, chunk))
+            .collect::<Result<Vec<_>, _>>()
+            .map(serde_json::Value::Array)
+    }
+
+    fn fields(&self, name: &str) -> Result<&Vec<Field>, StructError> {
+        match self.schemas.get(name) {
+            Some(Ok(fields)) => Ok(fields),
+            Some(Err(reason)) => Err(StructError::InvalidSchema {
+                name: name.to_string(),
+                reason: reason.clone(),
+            }),
+            None => Err(StructError::UnknownStruct(name.to_string())),
+        }
+    }
+
+    /// The size in bytes of the struct called `name`.
+    fn size_of(&self, name: &str) -> Result<usize, StructError> {
+        self.nested_size_of(name, &mut Vec::new())
+    }
+
+    /// The size in bytes of the struct called `name`, nested in the structs
+    /// in `resolving`.
+    fn nested_size_of(
+        &self,
+        name: &str,
+        resolving: &mut Vec<String>,
+    ) -> Result<usize, StructError> {
+        if resolving.iter().any(|outer| outer == name) {
+            return Err(StructError::Recursive(name.to_string()));
+        }
+        resolving.push(name.to_string());
+
+        let mut size = 0;
+        let mut bits = BitPacker::default();
+
+        for field in self.fields(name)? {
+            match (&field.field_type, field.bit_width) {
+                (FieldType::Primitive(primitive), Some(width)) => {
+                    if let Some(storage) = bits.allocate(primitive.size(), width) {
+                        size += storage;
+                    }
+                }
+                (FieldType::Primitive(primitive), None) => {
+                    bits.reset();
+                    size += primitive.size() * field.array_len.unwrap_or(1);
+                }
+                (FieldType::Struct(nested), _) => {
+                    bits.reset();
+                    size += self.nested_size_of(nested, resolving)? * field.array_len.unwrap_or(1);
+                }
+            }
+        }
+
+        resolving.pop();
+        Ok(size)
+    }
+
+    /// Decodes `bytes`, which must be exactly the size of the struct `name`.
+    ///
+    /// The size must have been checked with `size_of`, which also makes sure
+    /// that the struct doesn't contain itself.
+    fn decode_struct(&self, name: &str, bytes: &[u8]) -> Result<serde_json::Value, StructError> {
+        let mut object = Map::new();
+        let mut offset = 0;
+        let mut bits = BitPacker::default();
+
+        for field in self.fields(name)? {
+            let value = match (&field.field_type, field.bit_width) {
+                (FieldType::Primitive(primitive), Some(width)) => {
+                    let storage_size = primitive.size();
+                    if let Some(storage) = bits.allocate(storage_size, width) {
+                        offset += storage;
+                    }
+
+                    // the storage this bit-field lives in starts before the offset
+                    let start = offset - storage_size;
+                    let raw = read_uint(&bytes[start..offset]);
+                    let value = (raw >> bits.shift(width)) & mask(width);
+
+                    match primitive {
+                        Primitive::Bool => json!(value != 0),
+                        Primitive::Int(_) => json!(sign_extend(value, width)),
+                        _ => json!(value),
+                    }
+                }
+                (FieldType::Primitive(Primitive::Char), None) => {
+                    bits.reset();
+                    let len = field.array_len.unwrap_or(1);
+                    let text = String::from_utf8_lossy(&bytes[offset..offset + len]);
+                    offset += len;
+                    json!(text.trim_end_matches('\0'))
+                }
+                (FieldType::Primitive(primitive), None) => {
+                    bits.reset();
+                    let size = primitive.size();
+                    let mut read = || {
+                        let value = decode_primitive(*primitive, &bytes[offset..offset + size]);
+                        offset += size;
+                        value
+                    };
+
+                    match field.array_len {
+                        Some(len) => serde_json::Value::Array((0..len).map(|_| read()).collect()),
+                        None => read(),
+                    }
+                }
+                (FieldType::Struct(nested), _) => {
+                    bits.reset();
+                    let size = self.size_of(nested)?;
+                    let mut read = || {
+                        let value = self.decode_struct(nested, &bytes[offset..offset + size]);
+                        offset += size;
+                        value
+                    };
+
+                    match field.array_len {
+                        Some(len) => serde_json::Value::Array(
+                            (0..len).map(|_| read()).collect::<Result<_, _>>()?,
+                        ),
+                        None => read()?,
+                    }
+                }
+            };
+
+            let value = match (&field.enum_values, value.as_i64()) {
+                (Some(values), Some(number)) => {
+                    values.get(&number).map(|name| json!(name)).unwrap_or(value)
+                }
+                _ => value,
+            };
+
+            object.insert(field.name.clone(), value);
+        }
+
+        Ok(serde_json::Value::Object(object))
+    }
+}
+
+/// Tracks how consecutive bit-fields are packed into shared integers.
+///
+/// A bit-field starts a new integer when the previous field wasn't a
+/// bit-field, had a different storage size, or there aren't enough bits left.
+/// Bits are assigned starting from the least significant one.
+#[derive(Default)]
+struct BitPacker {
+    storage_size: usize,
+    used_bits: u32,
+}
+
+impl BitPacker {
+    /// Places a bit-field of `width` bits in storage of `storage_size` bytes.
+    ///
+    /// Returns the number of bytes to add to the struct if a new integer had
+    /// to be started.
+    fn allocate(&mut self, storage_size: usize, width: u32) -> Option<usize> {
+        let storage_bits = storage_size as u32 * 8;
+
+        if self.storage_size == storage_size && self.used_bits + width <= storage_bits {
+            self.used_bits += width;
+            None
+        } else {
+            self.storage_size = storage_size;
+            self.used_bits = width;
+            Some(storage_size)
+        }
+    }
+
+    /// The shift of the bit-field of `width` bits that was just allocated.
+    fn shift(&self, width: u32) -> u32 {
+        self.used_bits - width
+    }
+
+    fn reset(&mut self) {
+        self.storage_size = 0;
+        self.used_bits = 0;
+    }
+}
+
+fn mask(width: u32) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn sign_extend(value: u64, width: u32) -> i64 {
+    let shift = 64 - width.min(64);
+    ((value << shift) as i64) >> shift
+}
+
+/// Reads a little-endian unsigned integer of up to 8 bytes.
+fn read_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+fn decode_primitive(primitive: Primitive, bytes: &[u8]) -> serde_json::Value {
+    let raw = read_uint(bytes);
+
+    match primitive {
+        Primitive::Bool => json!(raw != 0),
+        Primitive::Char => json!((raw as u8 as char).to_string()),
+        Primitive::Int(size) => json!(sign_extend(raw, size as u32 * 8)),
+        Primitive::Uint(_) => json!(raw),
+        Primitive::Float => json!(f32::from_bits(raw as u32)),
+        Primitive::Double => json!(f64::from_bits(raw)),
+    }
+}
+
+/// The subscription needed to receive struct schemas.
+pub fn schema_subscription_set() -> SubscriptionSet {
+    SubscriptionSet::prefixed("struct-schemas", STRUCT_SCHEMA_PREFIX, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use network_tables::v4::{Topic, Type};
+    use network_tables::Value;
+
+    use super::super::topic_catalog::TopicCatalog;
+    use super::*;
+
+    fn registry(schemas: &[(&str, &str)]) -> StructRegistry {
+        let mut registry = StructRegistry::default();
+        for (name, schema) in schemas {
+            let topic_name = format!("{}{}", STRUCT_SCHEMA_PREFIX, name);
+            registry.add_schema(&topic_name, &Value::Binary(schema.as_bytes().to_vec()));
+        }
+        registry
+    }
+
+    #[test]
+    fn decodes_an_announced_pose2d() {
+        let mut catalog = TopicCatalog::default();
+        catalog.announce(&Topic {
+            name: "/SmartDashboard/Field/Robot".to_string(),
+            id: 7,
+            pubuid: None,
+            r#type: Type::Raw,
+            type_name: "struct:Pose2d".to_string(),
+            properties: None,
+        });
+        let registry = registry(&[
+            ("Pose2d", "Translation2d translation;Rotation2d rotation"),
+            ("Translation2d", "double x;double y"),
+            ("Rotation2d", "double value"),
+        ]);
+
+        let bytes: Vec<u8> = [1.5f64, -2.25, 0.5]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let type_name = catalog.type_name("/SmartDashboard/Field/Robot").unwrap();
+
+        assert_eq!(
+            registry.decode(&type_name, &bytes),
+            Some(Ok(json!({
+                "translation": { "x": 1.5, "y": -2.25 },
+                "rotation": { "value": 0.5 },
+            })))
+        );
+    }
+
+    #[test]
+    fn rejects_structs_that_contain_themselves() {
+        let registry = registry(&[
+            ("Node", "double value;Node next"),
+            ("Outer", "Inner inner"),
+            ("Inner", "double value;Outer outer[2]"),
+        ]);
+
+        assert_eq!(
+            registry.decode("struct:Node", &[0; 16]),
+            Some(Err(StructError::Recursive("Node".to_string())))
+        );
+        assert_eq!(
+            registry.decode("struct:Outer[]", &[0; 16]),
+            Some(Err(StructError::Recursive("Outer".to_string())))
+        );
+    }
+
+    #[test]
+    fn decodes_arrays_of_fields_and_of_structs() {
+        let registry = registry(&[
+            ("Module", "float speed;int16 angles[2];char name[4]"),
+            ("Modules", "Module modules[2]"),
+        ]);
+
+        let module = |speed: f32, angles: [i16; 2], name: &[u8; 4]| {
+            let mut bytes = speed.to_le_bytes().to_vec();
+            bytes.extend(angles.iter().flat_map(|angle| angle.to_le_bytes()));
+            bytes.extend(name);
+            bytes
+        };
+        let bytes = [
+            module(1.5, [90, -90], b"FL\0\0"),
+            module(-2.0, [0, 180], b"BACK"),
+        ]
+        .concat();
+        let expected = json!([
+            { "speed": 1.5, "angles": [90, -90], "name": "FL" },
+            { "speed": -2.0, "angles": [0, 180], "name": "BACK" },
+        ]);
+
+        assert_eq!(
+            registry.decode("struct:Module[]", &bytes),
+            Some(Ok(expected.clone()))
+        );
+        assert_eq!(
+            registry.decode("struct:Modules", &bytes),
+            Some(Ok(json!({ "modules": expected })))
+        );
+        assert_eq!(
+            registry.decode("struct:Module[]", &bytes[1..]),
+            Some(Err(StructError::WrongSize {
+                name: "Module[]".to_string(),
+                expected: 12,
+                actual: 23,
+            }))
+        );
+    }
+
+    #[test]
+    fn packs_consecutive_bit_fields_into_shared_integers() {
+        // `a`, `b` and `c` share the first byte, `d` doesn't fit in what is
+        // left of it, and `e` has a different storage size
+        let registry = registry(&[(
+            "Flags",
+            "uint8 a:3;bool b:1;uint8 c:4;uint8 d:6;uint16 e:9;double after",
+        )]);
+
+        // c = 1010, b = 1 and a = 101, from the most significant bit down
+        let mut bytes = vec![0b1010_1101, 0b0011_0001, 0x2c, 0x01];
+        bytes.extend(0.25f64.to_le_bytes());
+
+        assert_eq!(
+            registry.decode("struct:Flags", &bytes),
+            Some(Ok(json!({
+                "a": 0b101,
+                "b": true,
+                "c": 0b1010,
+                "d": 0b11_0001,
+                "e": 0x12c,
+                "after": 0.25,
+            })))
+        );
+    }
+
+    #[test]
+    fn sign_extends_signed_integers_and_bit_fields() {
+        let registry = registry(&[("Signed", "int8 small:4;int8 large:4;int16 whole;int64 long")]);
+
+        let mut bytes = vec![0b0111_1000];
+        bytes.extend((-300i16).to_le_bytes());
+        bytes.extend(i64::MIN.to_le_bytes());
+
+        assert_eq!(
+            registry.decode("struct:Signed", &bytes),
+            Some(Ok(json!({
+                "small": -8,
+                "large": 7,
+                "whole": -300,
+                "long": i64::MIN,
+            })))
+        );
+    }
+
+    #[test]
+    fn names_enum_values() {
+        let registry = registry(&[(
+            "Mode",
+            "enum {idle=0, intake=1, shoot=-1} int8 mode;enum{low=1,high=2} uint8 gear:2",
+        )]);
+
+        assert_eq!(
+            registry.decode("struct:Mode", &[0xff, 0b10]),
+            Some(Ok(json!({ "mode": "shoot", "gear": "high" })))
+        );
+        assert_eq!(
+            registry.decode("struct:Mode", &[5, 0b11]),
+            Some(Ok(json!({ "mode": 5, "gear": 3 })))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_bit_widths() {
+        for schema in [
+            "int8 x:0",
+            "int8 x:9",
+            "uint32 x:33",
+            "bool x:2",
+            "double x:3",
+            "Rotation2d x:3",
+        ] {
+            let registry = registry(&[("Bits", schema)]);
+
+            assert!(
+                matches!(
+                    registry.decode("struct:Bits", &[0; 8]),
+                    Some(Err(StructError::InvalidSchema { .. }))
+                ),
+                "accepted `{}`",
+                schema
+            );
+        }
+
+        let registry = registry(&[("Bits", "int64 x:64;bool y:1")]);
+        assert_eq!(
+            registry.decode("struct:Bits", &[0; 9]),
+            Some(Ok(json!({ "x": 0, "y": false })))
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use network_tables::v4::SubscriptionOptions;
+use serde::{Deserialize, Serialize};
+
+/// A group of topics subscribed to together, along with how their names are
+/// shown to the frontend.
+///
+/// `topics` are matched as prefixes when `prefix` is set, and as exact topic
+/// names otherwise. With `topics_only`, only announcements are received and
+/// no values. `periodic` is how often the server sends updates, in seconds.
+/// Before a message reaches the frontend, its topic name is
+/// replaced by its entry in `aliases` if it has one, and otherwise has
+/// `strip_prefix` removed from the front.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionSet {
+    pub name: String,
+    pub topics: Vec<String>,
+    pub prefix: bool,
+    pub all: bool,
+    pub topics_only: bool,
+    pub periodic: Option<f64>,
+    pub strip_prefix: Option<String>,
+    pub aliases: HashMap<String, String>,
+}
+
+impl SubscriptionSet {
+    /// A set receiving every value under `prefix`.
+    pub fn prefixed(name: &str, prefix: &str, strip_prefix: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            topics: vec![prefix.to_string()],
+            prefix: true,
+            all: true,
+            strip_prefix: strip_prefix.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// The subscription options this set is subscribed with.
+    ///
+    /// NT4 sends `periodic` as seconds with a fractional part, but the client
+    /// only takes whole seconds, so it is passed through the extra options
+    /// instead to keep rates like 0.02 intact.
+    pub fn options(&self) -> SubscriptionOptions {
+        SubscriptionOptions {
+            all: Some(self.all),
+            prefix: Some(self.prefix),
+            topics_only: Some(self.topics_only),
+            rest: self
+                .periodic
+                .map(|periodic| HashMap::from([("periodic".to_string(), periodic.into())])),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the full topic name `topic_name` belongs to this set.
+    pub fn matches(&self, topic_name: &str) -> bool {
+        self.topics.iter().any(|topic| {
+            if self.prefix {
+                topic_name.starts_with(topic.as_str())
+            } else {
+                topic_name == topic
+            }
+        })
+    }
+
+    /// The name `topic_name` is shown to the frontend as.
+    pub fn display_name(&self, topic_name: &str) -> String {
+        if let Some(alias) = self.aliases.get(topic_name) {
+            return alias.clone();
+        }
+
+        match &self.strip_prefix {
+            Some(strip_prefix) => topic_name
+                .strip_prefix(strip_prefix.as_str())
+                .unwrap_or(topic_name)
+                .to_string(),
+            None => topic_name.to_string(),
+        }
+    }
+}
+
+/// The sets subscribed to when none are configured.
+///
+/// SmartDashboard values reach the frontend without their `/SmartDashboard/`
+/// prefix, so that keys like `voltage` can be used directly. Everything else
+/// keeps its full name.
+pub fn default_subscription_sets() -> Vec<SubscriptionSet> {
+    vec![
+        SubscriptionSet::prefixed(
+            "smartdashboard",
+            "/SmartDashboard",
+            Some("/SmartDashboard/"),
+        ),
+        SubscriptionSet::prefixed("fms", "/FMSInfo", None),
+    ]
+}
+
+/// Returns the name the full topic name `topic_name` is shown to the
+/// frontend as, using the first set it belongs to.
+pub fn display_name(sets: &[SubscriptionSet], topic_name: &str) -> String {
+    sets.iter()
+        .find(|set| set.matches(topic_name))
+        .map(|set| set.display_name(topic_name))
+        .unwrap_or_else(|| topic_name.to_string())
+}
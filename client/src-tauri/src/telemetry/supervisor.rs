@@ -0,0 +1,183 @@
+use std::sync::Mutex;
+
+use tauri::async_runtime::{spawn, JoinHandle};
+use tauri::AppHandle;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use super::config::TelemetryConfig;
+use super::replay::{replay_session, Replay, ReplayCommand};
+use super::robot_address::TelemetryTarget;
+use super::shared::TelemetryShared;
+use super::subscribe_topics;
+use super::subscription_sets::SubscriptionSet;
+use super::telemetry_status::ConnectionState;
+use super::watchdog::WatchdogConfig;
+
+/// Owns the background task running [`subscribe_topics`].
+///
+/// The supervisor is stored in Tauri's managed state so that commands can
+/// retarget, restart or pause telemetry while the app is running. Every
+/// restart aborts the previous task before spawning a new one, so there is
+/// never more than one client connected to the robot. Aborting a task drops
+/// its pipeline, which finishes the recording in progress.
+///
+/// A replay of a recorded session takes the place of the telemetry task until
+/// telemetry is restarted or paused.
+pub struct TelemetrySupervisor {
+    app_handle: AppHandle,
+    shared: TelemetryShared,
+    config: Mutex<TelemetryConfig>,
+    task: Mutex<Option<JoinHandle<()>>>,
+    replay: Mutex<Option<UnboundedSender<ReplayCommand>>>,
+}
+
+impl TelemetrySupervisor {
+    pub fn new(app_handle: AppHandle, config: TelemetryConfig) -> Self {
+        Self {
+            shared: TelemetryShared::new(app_handle.clone()),
+            app_handle,
+            config: Mutex::new(config),
+            task: Mutex::new(None),
+            replay: Mutex::new(None),
+        }
+    }
+
+    /// Returns the configuration the next telemetry session will use.
+    pub fn config(&self) -> TelemetryConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Returns the robot the supervisor is currently targeting.
+    pub fn target(&self) -> TelemetryTarget {
+        self.config.lock().unwrap().target.clone()
+    }
+
+    /// Returns the current state of the connection to the robot.
+    pub fn status(&self) -> ConnectionState {
+        self.shared.status.get()
+    }
+
+    /// Returns the state shared with the telemetry task.
+    pub fn shared(&self) -> &TelemetryShared {
+        &self.shared
+    }
+
+    /// Changes the robot to connect to and restarts telemetry with it.
+    pub fn set_target(&self, target: TelemetryTarget) {
+        tracing::info!("Retargeting telemetry to {:?}", target);
+        self.config.lock().unwrap().target = target;
+        self.restart();
+    }
+
+    /// Changes the freshness deadlines and restarts telemetry with them.
+    pub fn set_watchdog(&self, watchdog: WatchdogConfig) {
+        self.config.lock().unwrap().watchdog = watchdog;
+        self.restart();
+    }
+
+    /// Adds a subscription set, replacing any existing set with the same name,
+    /// and restarts telemetry with it.
+    pub fn add_subscription_set(&self, set: SubscriptionSet) {
+        {
+            let mut config = self.config.lock().unwrap();
+            match config
+                .subscriptions
+                .iter_mut()
+                .find(|existing| existing.name == set.name)
+            {
+                Some(existing) => *existing = set,
+                None => config.subscriptions.push(set),
+            }
+        }
+        self.restart();
+    }
+
+    /// Removes the subscription set called `name` and restarts telemetry
+    /// without it.
+    ///
+    /// Returns whether such a set existed.
+    pub fn remove_subscription_set(&self, name: &str) -> bool {
+        let removed = {
+            let mut config = self.config.lock().unwrap();
+            let before = config.subscriptions.len();
+            config.subscriptions.retain(|set| set.name != name);
+            config.subscriptions.len() != before
+        };
+
+        if removed {
+            self.restart();
+        }
+        removed
+    }
+
+    /// Aborts the running telemetry task, if any, and spawns a fresh one.
+    pub fn restart(&self) {
+        let config = self.config();
+        let app_handle = self.app_handle.clone();
+        let shared = self.shared.clone();
+
+        let mut task = self.task.lock().unwrap();
+        if let Some(previous) = task.take() {
+            previous.abort();
+        }
+        self.shared.publisher.detach();
+        self.end_replay();
+
+        *task = Some(spawn(async move {
+            subscribe_topics(app_handle, config, shared).await;
+        }));
+    }
+
+    /// Aborts the running telemetry task, if any, and replays `replay` in its
+    /// place.
+    pub fn start_replay(&self, replay: Replay) {
+        tracing::info!("Replaying {}", replay.path().display());
+        let config = self.config();
+        let app_handle = self.app_handle.clone();
+        let shared = self.shared.clone();
+        let (commands, receiver) = unbounded_channel();
+
+        let mut task = self.task.lock().unwrap();
+        if let Some(previous) = task.take() {
+            previous.abort();
+        }
+        self.shared.publisher.detach();
+        *self.replay.lock().unwrap() = Some(commands);
+
+        *task = Some(spawn(async move {
+            replay_session(app_handle, config, shared, replay, receiver).await;
+        }));
+    }
+
+    /// Sends `command` to the running replay.
+    pub fn control_replay(&self, command: ReplayCommand) -> Result<(), String> {
+        self.replay
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|commands| commands.send(command).ok())
+            .ok_or_else(|| "No replay is running".to_string())
+    }
+
+    fn end_replay(&self) {
+        self.replay.lock().unwrap().take();
+        self.shared.replay.lock().unwrap().take();
+        self.shared.replay_clock.lock().unwrap().take();
+    }
+
+    /// Aborts the running telemetry task, if any.
+    ///
+    /// Returns whether a task was running.
+    pub fn pause(&self) -> bool {
+        match self.task.lock().unwrap().take() {
+            Some(task) => {
+                task.abort();
+                self.shared.publisher.detach();
+                self.end_replay();
+                self.shared.status.set(ConnectionState::Idle);
+                true
+            }
+            None => false,
+        }
+    }
+}
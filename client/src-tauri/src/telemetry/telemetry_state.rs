@@ -0,0 +1,131 @@
+use network_tables::Value;
+use serde::{Deserialize, Serialize};
+
+/// The gear the robot is driving in, as published to the `gear` topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gear {
+    #[default]
+    Park,
+    Reverse,
+    Neutral,
+    Low,
+    Auto,
+    Drive,
+}
+
+impl Gear {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "park" => Some(Gear::Park),
+            "reverse" => Some(Gear::Reverse),
+            "neutral" => Some(Gear::Neutral),
+            "low" => Some(Gear::Low),
+            "auto" => Some(Gear::Auto),
+            "drive" => Some(Gear::Drive),
+            _ => None,
+        }
+    }
+}
+
+/// The acceleration profile of the robot, as published to the `acc-profile`
+/// topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Chill,
+    Ludicrous,
+    Cruise,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chill" => Some(Mode::Chill),
+            "ludicrous" => Some(Mode::Ludicrous),
+            "cruise" => Some(Mode::Cruise),
+            _ => None,
+        }
+    }
+}
+
+/// The values the dashboard displays, mirroring `TelemetryData` in the
+/// frontend.
+///
+/// This is the payload of the `telemetry_state` event. It is updated in place
+/// as messages arrive and emitted as a whole at the UI frame rate, so the
+/// frontend gets at most one update per frame no matter how many topics
+/// changed.
+///
+/// `speed_limit` isn't a topic, but is set from the speed governor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TelemetryState {
+    pub orientation: f64,
+    pub chassis_x_speed: f64,
+    pub chassis_y_speed: f64,
+    pub accx: f64,
+    pub accy: f64,
+    pub accz: f64,
+    pub jerk_x: f64,
+    pub jerk_y: f64,
+    pub voltage: f64,
+    pub acc_profile: Mode,
+    pub gear: Gear,
+    pub ebrake: bool,
+    pub reorient: bool,
+    pub gpws: bool,
+    pub speed_limit: f64,
+}
+
+impl TelemetryState {
+    /// Applies an update to the topic `topic_name`, which is the display name
+    /// the frontend knows it by.
+    ///
+    /// Returns whether the state changed. Unknown topics and values of the
+    /// wrong type are ignored.
+    pub fn update(&mut self, topic_name: &str, value: &Value) -> bool {
+        let before = self.clone();
+
+        match topic_name {
+            "orientation" => set_number(&mut self.orientation, value),
+            "chassis-x-speed" => set_number(&mut self.chassis_x_speed, value),
+            "chassis-y-speed" => set_number(&mut self.chassis_y_speed, value),
+            "accx" => set_number(&mut self.accx, value),
+            "accy" => set_number(&mut self.accy, value),
+            "accz" => set_number(&mut self.accz, value),
+            "jerk-x" => set_number(&mut self.jerk_x, value),
+            "jerk-y" => set_number(&mut self.jerk_y, value),
+            "voltage" => set_number(&mut self.voltage, value),
+            "acc-profile" => {
+                if let Some(mode) = value.as_str().and_then(Mode::from_name) {
+                    self.acc_profile = mode;
+                }
+            }
+            "gear" => {
+                if let Some(gear) = value.as_str().and_then(Gear::from_name) {
+                    self.gear = gear;
+                }
+            }
+            "ebrake" => set_bool(&mut self.ebrake, value),
+            "reorient" => set_bool(&mut self.reorient, value),
+            "gpws" => set_bool(&mut self.gpws, value),
+            _ => {}
+        }
+
+        *self != before
+    }
+}
+
+fn set_number(field: &mut f64, value: &Value) {
+    if let Some(number) = value.as_f64() {
+        *field = number;
+    }
+}
+
+fn set_bool(field: &mut bool, value: &Value) {
+    if let Some(b) = value.as_bool() {
+        *field = b;
+    }
+}
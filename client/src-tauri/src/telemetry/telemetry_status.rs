@@ -0,0 +1,75 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime, Wry};
+
+/// The state of the link between the backend and the robot.
+///
+/// This is the payload of the `telemetry_status` event, serialized with a
+/// `state` tag, e.g. `{ "state": "backoff", "attempt": 2, "retry_in": 1000 }`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// Telemetry is not running.
+    Idle,
+    /// Looking up the candidate robot addresses.
+    Resolving,
+    /// Dialing the candidate robot addresses.
+    Connecting,
+    /// Connected, waiting for the subscription to be accepted.
+    Subscribing,
+    /// Receiving data from the robot on `address`.
+    Live { address: String },
+    /// Playing back the recording at `path` instead of receiving live data.
+    Replaying { path: String },
+    /// Still connected, but the robot stopped sending data.
+    Stale,
+    /// Waiting `retry_in` milliseconds before making another attempt, after
+    /// `attempt` attempts have failed.
+    Backoff {
+        attempt: u32,
+        max_attempts: Option<u32>,
+        retry_in: u64,
+    },
+    /// The last attempt failed for `reason`.
+    Failed { reason: String },
+}
+
+/// Tracks the current [`ConnectionState`] and reports it to the frontend.
+///
+/// The `telemetry_status` event is only emitted when the state actually
+/// changes, so it can be set as often as is convenient.
+pub struct TelemetryStatus<R: Runtime = Wry> {
+    app_handle: AppHandle<R>,
+    state: Mutex<ConnectionState>,
+}
+
+impl<R: Runtime> TelemetryStatus<R> {
+    pub fn new(app_handle: AppHandle<R>) -> Self {
+        Self {
+            app_handle,
+            state: Mutex::new(ConnectionState::Idle),
+        }
+    }
+
+    /// Returns the current state.
+    pub fn get(&self) -> ConnectionState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Moves to `state`, emitting `telemetry_status` if it differs from the
+    /// current one.
+    pub fn set(&self, state: ConnectionState) {
+        let mut current = self.state.lock().unwrap();
+        if *current == state {
+            return;
+        }
+
+        tracing::debug!("telemetry status: {:?}", state);
+        *current = state.clone();
+
+        self.app_handle
+            .emit_all("telemetry_status", state)
+            .expect("Failed to emit telemetry_status event");
+    }
+}
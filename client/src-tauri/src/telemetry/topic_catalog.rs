@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use network_tables::v4::Topic;
+use network_tables::Value;
+use serde::Serialize;
+
+use super::subscription_sets::SubscriptionSet;
+
+/// Prefix of the meta topics the server publishes the publishers of each
+/// topic to, e.g. `$pub$/SmartDashboard/voltage`.
+const PUBLISHERS_META_PREFIX: &str = "$pub$";
+
+/// How strongly the update rate follows the latest interval between updates.
+const RATE_SMOOTHING: f64 = 0.2;
+
+/// Everything known about a single topic announced by the server.
+///
+/// `type_name` is the NT4 type string, like `double`, `string[]` or
+/// `struct:Pose2d`, and `update_rate` is a smoothed estimate of updates per
+/// second.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicInfo {
+    pub name: String,
+    pub type_name: String,
+    pub properties: serde_json::Value,
+    pub publisher_count: Option<usize>,
+    pub last_value: Option<Value>,
+    pub update_rate: f64,
+    #[serde(skip)]
+    last_update: Option<Instant>,
+}
+
+/// A live catalog of the topics the server has announced.
+///
+/// Announcements and unannouncements are received through the client's
+/// callbacks, while values and publisher counts arrive through the regular
+/// subscriptions. The catalog only remembers whether it changed, so that
+/// `topics_changed` can be emitted at a steady pace instead of once for every
+/// announcement when a client first connects.
+#[derive(Default)]
+pub struct TopicCatalog {
+    topics: HashMap<String, TopicInfo>,
+    changed: bool,
+}
+
+impl TopicCatalog {
+    /// Adds a topic the server announced.
+    pub fn announce(&mut self, topic: &Topic) {
+        if topic.name.starts_with('$') {
+            return;
+        }
+
+        let properties = serde_json::to_value(&topic.properties).unwrap_or_default();
+
+        self.insert(&topic.name, topic.type_name.clone(), properties);
+    }
+
+    /// Adds a topic known from a recording rather than an announcement.
+    pub fn announce_recorded(&mut self, name: &str, type_name: &str) {
+        self.insert(name, type_name.to_string(), serde_json::Value::Null);
+    }
+
+    fn insert(&mut self, name: &str, type_name: String, properties: serde_json::Value) {
+        let info = self
+            .topics
+            .entry(name.to_string())
+            .or_insert_with(|| TopicInfo {
+                name: name.to_string(),
+                type_name: String::new(),
+                properties: serde_json::Value::Null,
+                publisher_count: None,
+                last_value: None,
+                update_rate: 0.0,
+                last_update: None,
+            });
+        info.type_name = type_name;
+        info.properties = properties;
+
+        self.changed = true;
+    }
+
+    /// Removes a topic the server no longer has any publishers for.
+    pub fn unannounce(&mut self, name: &str) {
+        if self.topics.remove(name).is_some() {
+            self.changed = true;
+        }
+    }
+
+    /// Records a message received on the full topic name `topic_name`.
+    ///
+    /// Returns whether the message was catalog metadata, which should not be
+    /// passed on to the rest of the pipeline.
+    pub fn record(&mut self, topic_name: &str, value: &Value, now: Instant) -> bool {
+        if let Some(name) = topic_name.strip_prefix(PUBLISHERS_META_PREFIX) {
+            if let Some(info) = self.topics.get_mut(name) {
+                info.publisher_count = count_publishers(value);
+                self.changed = true;
+            }
+            return true;
+        }
+
+        if let Some(info) = self.topics.get_mut(topic_name) {
+            if let Some(last_update) = info.last_update {
+                let interval = now.duration_since(last_update).as_secs_f64();
+                if interval > 0.0 {
+                    info.update_rate += RATE_SMOOTHING * (1.0 / interval - info.update_rate);
+                }
+            }
+
+            info.last_update = Some(now);
+            info.last_value = Some(value.clone());
+        }
+
+        false
+    }
+
+    /// Returns every topic, sorted by name.
+    pub fn list(&self) -> Vec<TopicInfo> {
+        let mut topics: Vec<TopicInfo> = self.topics.values().cloned().collect();
+        topics.sort_by(|a, b| a.name.cmp(&b.name));
+        topics
+    }
+
+    pub fn get(&self, name: &str) -> Option<TopicInfo> {
+        self.topics.get(name).cloned()
+    }
+
+    /// Returns the NT4 type string the topic was announced with.
+    pub fn type_name(&self, name: &str) -> Option<String> {
+        self.topics.get(name).map(|info| info.type_name.clone())
+    }
+
+    /// Returns whether topics were announced or unannounced since the last
+    /// call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Forgets every topic, e.g. before connecting to a different robot.
+    pub fn clear(&mut self) {
+        self.topics.clear();
+        self.changed = true;
+    }
+}
+
+/// The subscriptions the catalog needs on top of the configured ones.
+///
+/// The first only asks for announcements of every topic, without their
+/// values, and the second receives the publishers of every topic.
+pub fn catalog_subscription_sets() -> Vec<SubscriptionSet> {
+    vec![
+        SubscriptionSet {
+            name: "catalog".to_string(),
+            topics: vec!["".to_string()],
+            prefix: true,
+            topics_only: true,
+            ..Default::default()
+        },
+        SubscriptionSet {
+            name: "catalog-publishers".to_string(),
+            topics: vec![PUBLISHERS_META_PREFIX.to_string()],
+            prefix: true,
+            all: true,
+            ..Default::default()
+        },
+    ]
+}
+
+/// Counts the publishers listed in a `$pub$` meta topic.
+///
+/// The list is MessagePack encoded, and arrives either already decoded or as
+/// raw bytes depending on how the server typed it.
+fn count_publishers(value: &Value) -> Option<usize> {
+    match value {
+        Value::Array(publishers) => Some(publishers.len()),
+        Value::Binary(bytes) => match rmpv::decode::read_value(&mut bytes.as_slice()) {
+            Ok(Value::Array(publishers)) => Some(publishers.len()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
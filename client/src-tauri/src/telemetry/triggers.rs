@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// A condition on the latest values of one or more topics.
+///
+/// Topics are referred to by the name the frontend knows them by, e.g. `gpws`
+/// rather than `/SmartDashboard/gpws`. A condition on a topic that hasn't
+/// been received yet is false.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// The topic is equal to `value`. Numbers compare equal regardless of
+    /// whether they were published as integers or doubles.
+    Equals {
+        topic: String,
+        value: serde_json::Value,
+    },
+    /// The topic is above `threshold`. Once true, it stays true until the
+    /// topic drops to `threshold - hysteresis`.
+    Above {
+        topic: String,
+        threshold: f64,
+        #[serde(default)]
+        hysteresis: f64,
+    },
+    /// The topic is below `threshold`. Once true, it stays true until the
+    /// topic rises to `threshold + hysteresis`.
+    Below {
+        topic: String,
+        threshold: f64,
+        #[serde(default)]
+        hysteresis: f64,
+    },
+    All {
+        conditions: Vec<Condition>,
+    },
+    Any {
+        conditions: Vec<Condition>,
+    },
+    Not {
+        condition: Box<Condition>,
+    },
+}
+
+impl Condition {
+    /// Evaluates the condition against `values`.
+    ///
+    /// `active` is whether the rule is currently active, which decides which
+    /// side of a hysteresis band thresholds use.
+    fn evaluate(&self, values: &HashMap<String, serde_json::Value>, active: bool) -> bool {
+        match self {
+            Condition::Equals { topic, value } => match values.get(topic) {
+                Some(current) => match (current.as_f64(), value.as_f64()) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => current == value,
+                },
+                None => false,
+            },
+            Condition::Above {
+                topic,
+                threshold,
+                hysteresis,
+            } => match values.get(topic).and_then(|value| value.as_f64()) {
+                Some(value) if active => value > threshold - hysteresis,
+                Some(value) => value > *threshold,
+                None => false,
+            },
+            Condition::Below {
+                topic,
+                threshold,
+                hysteresis,
+            } => match values.get(topic).and_then(|value| value.as_f64()) {
+                Some(value) if active => value < threshold + hysteresis,
+                Some(value) => value < *threshold,
+                None => false,
+            },
+            Condition::All { conditions } => conditions
+                .iter()
+                .all(|condition| condition.evaluate(values, active)),
+            Condition::Any { conditions } => conditions
+                .iter()
+                .any(|condition| condition.evaluate(values, active)),
+            Condition::Not { condition } => !condition.evaluate(values, !active),
+        }
+    }
+
+    /// Returns whether the condition depends on `topic`.
+    fn mentions(&self, topic: &str) -> bool {
+        match self {
+            Condition::Equals { topic: t, .. }
+            | Condition::Above { topic: t, .. }
+            | Condition::Below { topic: t, .. } => t == topic,
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                conditions.iter().any(|condition| condition.mentions(topic))
+            }
+            Condition::Not { condition } => condition.mentions(topic),
+        }
+    }
+}
+
+/// Which transitions of a rule's condition emit its event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Edge {
+    /// The condition became true.
+    #[default]
+    Rising,
+    /// The condition became false.
+    Falling,
+    Both,
+}
+
+/// Emits `event` whenever `condition` crosses `edge`.
+///
+/// The condition has to hold its new state for `hold_ms` before the
+/// transition counts, which debounces noisy topics. The event's payload is
+/// `payload` if given, and otherwise whether the condition is now true.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerRule {
+    pub event: String,
+    pub condition: Condition,
+    #[serde(default)]
+    pub edge: Edge,
+    #[serde(default)]
+    pub hold_ms: u64,
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+}
+
+impl TriggerRule {
+    /// Checks that the rule's event can be emitted to the frontend, which
+    /// only accepts event names made of alphanumeric characters, `-`, `/`,
+    /// `:` and `_`.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = !self.event.is_empty()
+            && self
+                .event
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '/' | ':' | '_'));
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Invalid event name `{}`", self.event))
+        }
+    }
+}
+
+/// The rules used when none are configured.
+pub fn default_trigger_rules() -> Vec<TriggerRule> {
+    vec![TriggerRule {
+        event: "telemetry_gpws".to_string(),
+        condition: Condition::Equals {
+            topic: "gpws".to_string(),
+            value: serde_json::Value::Bool(true),
+        },
+        edge: Edge::Both,
+        hold_ms: 0,
+        payload: None,
+    }]
+}
+
+/// An event emitted by a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggerEvent {
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+struct RuleState {
+    rule: TriggerRule,
+    active: bool,
+    /// When the condition started disagreeing with `active`, if it currently
+    /// does.
+    changed_since: Option<Instant>,
+}
+
+/// Evaluates [`TriggerRule`]s against the latest value of every topic.
+///
+/// The engine doesn't know about Tauri. It is fed values with
+/// [`TriggerEngine::update`] and returns the events to emit, and
+/// [`TriggerEngine::check`] should be called periodically so that rules with a
+/// hold time fire even when no new values arrive.
+pub struct TriggerEngine {
+    rules: Vec<RuleState>,
+    values: HashMap<String, serde_json::Value>,
+}
+
+impl TriggerEngine {
+    pub fn new(rules: Vec<TriggerRule>) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| RuleState {
+                    rule,
+                    active: false,
+                    changed_since: None,
+                })
+                .collect(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Records a new value of `topic` and returns the events of the rules it
+    /// triggered.
+    pub fn update(
+        &mut self,
+        topic: &str,
+        value: &network_tables::Value,
+        now: Instant,
+    ) -> Vec<TriggerEvent> {
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(_) => return Vec::new(),
+        };
+        self.values.insert(topic.to_string(), value);
+
+        let values = &self.values;
+        self.rules
+            .iter_mut()
+            .filter(|state| state.rule.condition.mentions(topic))
+            .filter_map(|state| state.evaluate(values, now))
+            .collect()
+    }
+
+    /// Returns the events of rules whose hold time ran out since the last
+    /// update.
+    pub fn check(&mut self, now: Instant) -> Vec<TriggerEvent> {
+        let values = &self.values;
+        self.rules
+            .iter_mut()
+            .filter(|state| state.changed_since.is_some())
+            .filter_map(|state| state.evaluate(values, now))
+            .collect()
+    }
+}
+
+impl RuleState {
+    fn evaluate(
+        &mut self,
+        values: &HashMap<String, serde_json::Value>,
+        now: Instant,
+    ) -> Option<TriggerEvent> {
+        let holds = self.rule.condition.evaluate(values, self.active);
+        if holds == self.active {
+            self.changed_since = None;
+            return None;
+        }
+
+        let since = *self.changed_since.get_or_insert(now);
+        if now.duration_since(since) < Duration::from_millis(self.rule.hold_ms) {
+            return None;
+        }
+
+        self.active = holds;
+        self.changed_since = None;
+
+        let fires = match self.rule.edge {
+            Edge::Rising => holds,
+            Edge::Falling => !holds,
+            Edge::Both => true,
+        };
+
+        fires.then(|| TriggerEvent {
+            event: self.rule.event.clone(),
+            payload: self
+                .rule
+                .payload
+                .clone()
+                .unwrap_or(serde_json::Value::Bool(holds)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use network_tables::Value;
+    use serde_json::json;
+
+    use super::*;
+
+    fn rule(condition: Condition, edge: Edge, hold_ms: u64) -> TriggerRule {
+        TriggerRule {
+            event: "test_event".to_string(),
+            condition,
+            edge,
+            hold_ms,
+            payload: None,
+        }
+    }
+
+    fn equals(topic: &str, value: serde_json::Value) -> Condition {
+        Condition::Equals {
+            topic: topic.to_string(),
+            value,
+        }
+    }
+
+    fn above(topic: &str, threshold: f64, hysteresis: f64) -> Condition {
+        Condition::Above {
+            topic: topic.to_string(),
+            threshold,
+            hysteresis,
+        }
+    }
+
+    fn below(topic: &str, threshold: f64, hysteresis: f64) -> Condition {
+        Condition::Below {
+            topic: topic.to_string(),
+            threshold,
+            hysteresis,
+        }
+    }
+
+    /// Feeds `values` of `topic` to `engine` and returns the payloads of the
+    /// events fired by each.
+    fn feed(
+        engine: &mut TriggerEngine,
+        topic: &str,
+        values: &[Value],
+        now: Instant,
+    ) -> Vec<Vec<serde_json::Value>> {
+        values
+            .iter()
+            .map(|value| {
+                engine
+                    .update(topic, value, now)
+                    .into_iter()
+                    .map(|event| event.payload)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validates_event_names() {
+        let named = |event: &str| TriggerRule {
+            event: event.to_string(),
+            ..rule(equals("gpws", json!(true)), Edge::Rising, 0)
+        };
+
+        for event in ["telemetry_gpws", "battery/low:critical-2"] {
+            assert_eq!(named(event).validate(), Ok(()));
+        }
+        for event in ["", "gpws warning", "gpws.active", "gpws!"] {
+            assert!(named(event).validate().is_err(), "accepted `{}`", event);
+        }
+        assert!(default_trigger_rules()
+            .iter()
+            .all(|rule| rule.validate().is_ok()));
+    }
+
+    #[test]
+    fn rising_edge_fires_once_per_transition() {
+        let mut engine =
+            TriggerEngine::new(vec![rule(equals("gpws", json!(true)), Edge::Rising, 0)]);
+
+        let fired = feed(
+            &mut engine,
+            "gpws",
+            &[
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Boolean(true),
+            ],
+            Instant::now(),
+        );
+
+        assert_eq!(
+            fired,
+            [vec![json!(true)], vec![], vec![], vec![json!(true)]]
+        );
+    }
+
+    #[test]
+    fn falling_and_both_edges() {
+        let values = [Value::Boolean(true), Value::Boolean(false)];
+
+        let mut falling =
+            TriggerEngine::new(vec![rule(equals("gpws", json!(true)), Edge::Falling, 0)]);
+        assert_eq!(
+            feed(&mut falling, "gpws", &values, Instant::now()),
+            [vec![], vec![json!(false)]]
+        );
+
+        let mut both = TriggerEngine::new(vec![rule(equals("gpws", json!(true)), Edge::Both, 0)]);
+        assert_eq!(
+            feed(&mut both, "gpws", &values, Instant::now()),
+            [vec![json!(true)], vec![json!(false)]]
+        );
+    }
+
+    #[test]
+    fn above_stays_active_within_the_hysteresis_band() {
+        let mut engine = TriggerEngine::new(vec![rule(above("speed", 10.0, 2.0), Edge::Both, 0)]);
+
+        let fired = feed(
+            &mut engine,
+            "speed",
+            &[
+                Value::F64(9.0),
+                Value::F64(10.5),
+                Value::F64(9.0),
+                Value::F64(8.5),
+                Value::F64(7.5),
+                Value::F64(9.5),
+            ],
+            Instant::now(),
+        );
+
+        assert_eq!(
+            fired,
+            [
+                vec![],
+                vec![json!(true)],
+                vec![],
+                vec![],
+                vec![json!(false)],
+                vec![]
+            ]
+        );
+    }
+
+    #[test]
+    fn below_stays_active_within_the_hysteresis_band() {
+        let mut engine = TriggerEngine::new(vec![rule(below("voltage", 7.0, 0.5), Edge::Both, 0)]);
+
+        let fired = feed(
+            &mut engine,
+            "voltage",
+            &[
+                Value::F64(6.9),
+                Value::F64(7.2),
+                Value::F64(7.6),
+                Value::F64(6.95),
+            ],
+            Instant::now(),
+        );
+
+        assert_eq!(
+            fired,
+            [
+                vec![json!(true)],
+                vec![],
+                vec![json!(false)],
+                vec![json!(true)]
+            ]
+        );
+    }
+
+    #[test]
+    fn hold_time_debounces_and_fires_on_check() {
+        let mut engine =
+            TriggerEngine::new(vec![rule(equals("gpws", json!(true)), Edge::Rising, 100)]);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(engine
+            .update("gpws", &Value::Boolean(true), at(0))
+            .is_empty());
+        // a blip back to false restarts the hold time
+        assert!(engine
+            .update("gpws", &Value::Boolean(false), at(50))
+            .is_empty());
+        assert!(engine
+            .update("gpws", &Value::Boolean(true), at(60))
+            .is_empty());
+        assert!(engine.check(at(150)).is_empty());
+
+        let events = engine.check(at(160));
+        assert_eq!(
+            events,
+            [TriggerEvent {
+                event: "test_event".to_string(),
+                payload: json!(true),
+            }]
+        );
+        assert!(engine.check(at(500)).is_empty());
+    }
+
+    #[test]
+    fn equals_compares_integers_and_doubles() {
+        let mut engine = TriggerEngine::new(vec![
+            rule(equals("gear", json!(3)), Edge::Rising, 0),
+            rule(equals("mode", json!("ludicrous")), Edge::Rising, 0),
+        ]);
+        let now = Instant::now();
+
+        assert_eq!(engine.update("gear", &Value::F64(3.0), now).len(), 1);
+        assert!(engine
+            .update("mode", &Value::String("chill".into()), now)
+            .is_empty());
+        assert_eq!(
+            engine
+                .update("mode", &Value::String("ludicrous".into()), now)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn combinations_of_conditions() {
+        let mut engine = TriggerEngine::new(vec![TriggerRule {
+            payload: Some(json!("slow down")),
+            ..rule(
+                Condition::All {
+                    conditions: vec![
+                        above("speed", 5.0, 0.0),
+                        Condition::Any {
+                            conditions: vec![
+                                equals("gear", json!("low")),
+                                Condition::Not {
+                                    condition: Box::new(equals("brake", json!(false))),
+                                },
+                            ],
+                        },
+                    ],
+                },
+                Edge::Rising,
+                0,
+            )
+        }]);
+        let now = Instant::now();
+
+        assert!(engine
+            .update("brake", &Value::Boolean(false), now)
+            .is_empty());
+        assert!(engine
+            .update("gear", &Value::String("high".into()), now)
+            .is_empty());
+        assert!(engine.update("speed", &Value::F64(6.0), now).is_empty());
+
+        let events = engine.update("gear", &Value::String("low".into()), now);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].payload, json!("slow down"));
+
+        // topics the rule doesn't mention don't evaluate it
+        assert!(engine.update("voltage", &Value::F64(12.0), now).is_empty());
+    }
+}
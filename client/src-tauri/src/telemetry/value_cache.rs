@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use network_tables::Value;
+use serde::Serialize;
+
+use super::epoch::epoch_ms;
+use super::watchdog::WatchdogConfig;
+
+/// The last value received on a topic, as returned by
+/// `get_telemetry_snapshot`.
+///
+/// `timestamp` is when the value was received, in milliseconds since the Unix
+/// epoch, and `age_ms` is how long ago that was.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CachedValue {
+    pub topic: String,
+    pub value: Value,
+    pub timestamp: u64,
+    pub age_ms: u64,
+    pub stale: bool,
+}
+
+struct Entry {
+    value: Value,
+    timestamp: u64,
+    received: Instant,
+}
+
+/// Keeps the last value received on every topic, so windows that open or
+/// reload while connected don't have to wait for each topic to change.
+#[derive(Default)]
+pub struct ValueCache {
+    entries: HashMap<String, Entry>,
+}
+
+impl ValueCache {
+    /// Records `value` as the latest value of `topic`.
+    pub fn record(&mut self, topic: &str, value: &Value, now: Instant) {
+        self.entries.insert(
+            topic.to_string(),
+            Entry {
+                value: value.clone(),
+                timestamp: epoch_ms(),
+                received: now,
+            },
+        );
+    }
+
+    /// Returns every cached value.
+    ///
+    /// A value is stale if its topic is past the deadline `watchdog` gives it,
+    /// or if `link_stale` says nothing can be trusted to be current.
+    pub fn snapshot(
+        &self,
+        now: Instant,
+        watchdog: &WatchdogConfig,
+        link_stale: bool,
+    ) -> Vec<CachedValue> {
+        let mut values: Vec<CachedValue> = self
+            .entries
+            .iter()
+            .map(|(topic, entry)| {
+                let age = now.duration_since(entry.received);
+                let past_deadline = watchdog
+                    .topic_deadlines_ms
+                    .get(topic)
+                    .map_or(false, |deadline_ms| {
+                        age > Duration::from_millis(*deadline_ms)
+                    });
+
+                CachedValue {
+                    topic: topic.clone(),
+                    value: entry.value.clone(),
+                    timestamp: entry.timestamp,
+                    age_ms: age.as_millis() as u64,
+                    stale: link_stale || past_deadline,
+                }
+            })
+            .collect();
+
+        values.sort_by(|a, b| a.topic.cmp(&b.topic));
+        values
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
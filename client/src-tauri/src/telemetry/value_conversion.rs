@@ -0,0 +1,32 @@
+use network_tables::Value;
+
+/// Converts decoded JSON into a NetworkTables value, so that it can take the
+/// place of the raw bytes it was decoded from.
+///
+/// Objects become MessagePack maps with string keys, which serialize back
+/// into the same JSON objects when emitted to the frontend.
+pub fn json_to_value(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Number(number) => {
+            if let Some(number) = number.as_i64() {
+                Value::from(number)
+            } else if let Some(number) = number.as_u64() {
+                Value::from(number)
+            } else {
+                Value::from(number.as_f64().unwrap_or_default())
+            }
+        }
+        serde_json::Value::String(string) => Value::from(string),
+        serde_json::Value::Array(values) => {
+            Value::Array(values.into_iter().map(json_to_value).collect())
+        }
+        serde_json::Value::Object(object) => Value::Map(
+            object
+                .into_iter()
+                .map(|(key, value)| (Value::from(key), json_to_value(value)))
+                .collect(),
+        ),
+    }
+}
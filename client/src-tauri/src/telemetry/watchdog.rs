@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Deadlines used by the [`FreshnessWatchdog`].
+///
+/// `global_deadline_ms` is how long the robot may go without sending anything
+/// at all before the whole link is considered stale. `topic_deadlines_ms`
+/// gives individual topics their own deadline. Topics without one are never
+/// marked stale on their own, since most of them are only sent on change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub global_deadline_ms: u64,
+    pub topic_deadlines_ms: HashMap<String, u64>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            global_deadline_ms: 2000,
+            topic_deadlines_ms: HashMap::new(),
+        }
+    }
+}
+
+/// A change in freshness noticed by the watchdog.
+///
+/// `topic` is `None` when the change applies to the link as a whole.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FreshnessChange {
+    Stale { topic: Option<String>, age_ms: u64 },
+    Fresh { topic: Option<String> },
+}
+
+/// Tracks when each topic was last updated and notices when data stops
+/// arriving.
+///
+/// A TCP connection to a robot whose code has hung stays open, so the
+/// subscription never ends. The watchdog catches this case by comparing the
+/// age of the last update against the configured deadlines.
+pub struct FreshnessWatchdog {
+    config: WatchdogConfig,
+    started: Instant,
+    last_update: HashMap<String, Instant>,
+    last_any: Instant,
+    stale_topics: HashSet<String>,
+    globally_stale: bool,
+}
+
+impl FreshnessWatchdog {
+    pub fn new(config: WatchdogConfig, now: Instant) -> Self {
+        Self {
+            config,
+            started: now,
+            last_update: HashMap::new(),
+            last_any: now,
+            stale_topics: HashSet::new(),
+            globally_stale: false,
+        }
+    }
+
+    /// Records an update to `topic`, returning the staleness it cleared.
+    pub fn record(&mut self, topic: &str, now: Instant) -> Vec<FreshnessChange> {
+        let mut changes = Vec::new();
+
+        self.last_any = now;
+        self.last_update.insert(topic.to_string(), now);
+
+        if self.globally_stale {
+            self.globally_stale = false;
+            changes.push(FreshnessChange::Fresh { topic: None });
+        }
+
+        if self.stale_topics.remove(topic) {
+            changes.push(FreshnessChange::Fresh {
+                topic: Some(topic.to_string()),
+            });
+        }
+
+        changes
+    }
+
+    /// Checks every deadline, returning the topics that just went stale.
+    pub fn check(&mut self, now: Instant) -> Vec<FreshnessChange> {
+        let mut changes = Vec::new();
+
+        let age = now.duration_since(self.last_any);
+        if !self.globally_stale && age > Duration::from_millis(self.config.global_deadline_ms) {
+            self.globally_stale = true;
+            changes.push(FreshnessChange::Stale {
+                topic: None,
+                age_ms: age.as_millis() as u64,
+            });
+        }
+
+        for (topic, deadline_ms) in &self.config.topic_deadlines_ms {
+            if self.stale_topics.contains(topic) {
+                continue;
+            }
+
+            // topics that were never received count from the start of the session
+            let last_update = self.last_update.get(topic).unwrap_or(&self.started);
+            let age = now.duration_since(*last_update);
+            if age > Duration::from_millis(*deadline_ms) {
+                self.stale_topics.insert(topic.clone());
+                changes.push(FreshnessChange::Stale {
+                    topic: Some(topic.clone()),
+                    age_ms: age.as_millis() as u64,
+                });
+            }
+        }
+
+        changes
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use network_tables::Value;
+
+/// Magic bytes every `.wpilog` file starts with.
+const MAGIC: &[u8] = b"WPILOG";
+
+/// Version 1.0 of the format, which is what AdvantageScope reads.
+const VERSION: u16 = 0x0100;
+
+/// Entry id of control records.
+const CONTROL_ENTRY: u32 = 0;
+
+/// Control record types.
+const CONTROL_START: u8 = 0;
+const CONTROL_FINISH: u8 = 1;
+
+/// An entry of a log, as started by a control record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub name: String,
+    pub type_name: String,
+}
+
+/// A value appended to an entry, with `entry` the index of the entry in
+/// [`WpilogLog::entries`] and `timestamp` in microseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub entry: usize,
+    pub timestamp: u64,
+    pub value: Value,
+}
+
+/// Everything read from a `.wpilog` file, with the records sorted by
+/// timestamp.
+#[derive(Debug, Clone, Default)]
+pub struct WpilogLog {
+    pub entries: Vec<LogEntry>,
+    pub records: Vec<LogRecord>,
+}
+
+/// Writes records in WPILib's DataLog format.
+///
+/// Entries are started lazily, the first time a value is appended to them, so
+/// callers only deal with names and types.
+pub struct WpilogWriter<W: Write> {
+    out: W,
+    entries: HashMap<String, u32>,
+    next_entry: u32,
+}
+
+impl<W: Write> WpilogWriter<W> {
+    /// Writes the file header to `out`, with `extra_header` as free-form
+    /// metadata.
+    pub fn new(mut out: W, extra_header: &str) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(extra_header.len() as u32).to_le_bytes())?;
+        out.write_all(extra_header.as_bytes())?;
+
+        Ok(Self {
+            out,
+            entries: HashMap::new(),
+            next_entry: 1,
+        })
+    }
+
+    /// Appends `value` to the entry `name`, starting the entry with
+    /// `type_name` if this is its first value. `timestamp` is in microseconds.
+    ///
+    /// Values that can't be encoded as `type_name` are skipped.
+    pub fn append(
+        &mut self,
+        name: &str,
+        type_name: &str,
+        value: &Value,
+        timestamp: u64,
+    ) -> io::Result<()> {
+        let payload = match encode_value(type_name, value) {
+            Some(payload) => payload,
+            None => return Ok(()),
+        };
+
+        let entry = match self.entries.get(name) {
+            Some(entry) => *entry,
+            None => self.start_entry(name, type_name, timestamp)?,
+        };
+
+        write_record(&mut self.out, entry, timestamp, &payload)
+    }
+
+    fn start_entry(&mut self, name: &str, type_name: &str, timestamp: u64) -> io::Result<u32> {
+        let entry = self.next_entry;
+        self.next_entry += 1;
+        self.entries.insert(name.to_string(), entry);
+
+        let mut payload = vec![CONTROL_START];
+        payload.extend_from_slice(&entry.to_le_bytes());
+        for field in [name, type_name, ""] {
+            payload.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            payload.extend_from_slice(field.as_bytes());
+        }
+
+        write_record(&mut self.out, CONTROL_ENTRY, timestamp, &payload)?;
+        Ok(entry)
+    }
+
+    /// Finishes every entry and flushes the output.
+    pub fn finish(mut self, timestamp: u64) -> io::Result<()> {
+        let entries: Vec<u32> = self.entries.values().copied().collect();
+        for entry in entries {
+            let mut payload = vec![CONTROL_FINISH];
+            payload.extend_from_slice(&entry.to_le_bytes());
+            write_record(&mut self.out, CONTROL_ENTRY, timestamp, &payload)?;
+        }
+
+        self.out.flush()
+    }
+}
+
+/// Reads a log written in WPILib's DataLog format, by [`WpilogWriter`] or by
+/// the roboRIO.
+///
+/// Entry ids may be reused once finished, so every start record gets an entry
+/// of its own. Records of unknown entries are skipped, and a truncated last
+/// record, e.g. from a robot that lost power, ends the log.
+pub fn read_wpilog(bytes: &[u8]) -> io::Result<WpilogLog> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    if bytes.len() < 12 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid("Not a .wpilog file"));
+    }
+    let version = u16::from_le_bytes([bytes[6], bytes[7]]);
+    if version >> 8 != VERSION >> 8 {
+        return Err(invalid("Unsupported .wpilog version"));
+    }
+    let extra_header_len = read_int(&bytes[8..12]) as usize;
+
+    let mut log = WpilogLog::default();
+    let mut active: HashMap<u32, usize> = HashMap::new();
+    let mut rest = bytes.get(12 + extra_header_len..).unwrap_or_default();
+
+    while let Some((entry, timestamp, payload, next)) = read_record(rest) {
+        rest = next;
+
+        if entry != CONTROL_ENTRY {
+            if let Some(&index) = active.get(&entry) {
+                let type_name = &log.entries[index].type_name;
+                if let Some(value) = decode_value(type_name, payload) {
+                    log.records.push(LogRecord {
+                        entry: index,
+                        timestamp,
+                        value,
+                    });
+                }
+            }
+            continue;
+        }
+
+        match payload.first() {
+            Some(&CONTROL_START) => {
+                let mut fields = payload.get(1..).unwrap_or_default();
+                let id = read_int(fields.get(..4).unwrap_or_default()) as u32;
+                fields = fields.get(4..).unwrap_or_default();
+                let (name, fields) =
+                    read_string(fields).ok_or_else(|| invalid("Bad start record"))?;
+                let (type_name, _) =
+                    read_string(fields).ok_or_else(|| invalid("Bad start record"))?;
+
+                active.insert(id, log.entries.len());
+                log.entries.push(LogEntry { name, type_name });
+            }
+            Some(&CONTROL_FINISH) => {
+                let id = read_int(payload.get(1..5).unwrap_or_default()) as u32;
+                active.remove(&id);
+            }
+            // metadata updates
+            _ => {}
+        }
+    }
+
+    log.records.sort_by_key(|record| record.timestamp);
+    Ok(log)
+}
+
+/// Reads a little-endian integer of up to eight bytes.
+fn read_int(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | byte as u64)
+}
+
+/// Reads a string prefixed with its 32-bit length, returning it with the
+/// bytes that follow it.
+fn read_string(bytes: &[u8]) -> Option<(String, &[u8])> {
+    let len = read_int(bytes.get(..4)?) as usize;
+    let string = bytes.get(4..4 + len)?;
+    Some((
+        String::from_utf8_lossy(string).into_owned(),
+        bytes.get(4 + len..)?,
+    ))
+}
+
+/// Reads the record at the start of `bytes`, as written by [`write_record`].
+///
+/// Returns its entry id, timestamp and payload with the bytes that follow it,
+/// or `None` if `bytes` doesn't hold a whole record.
+fn read_record(bytes: &[u8]) -> Option<(u32, u64, &[u8], &[u8])> {
+    let header = *bytes.first()? as usize;
+    let entry_len = (header & 0x3) + 1;
+    let size_len = ((header >> 2) & 0x3) + 1;
+    let timestamp_len = ((header >> 4) & 0x7) + 1;
+
+    let mut offset = 1;
+    let entry = read_int(bytes.get(offset..offset + entry_len)?) as u32;
+    offset += entry_len;
+    let size = read_int(bytes.get(offset..offset + size_len)?) as usize;
+    offset += size_len;
+    let timestamp = read_int(bytes.get(offset..offset + timestamp_len)?);
+    offset += timestamp_len;
+    let payload = bytes.get(offset..offset + size)?;
+
+    Some((entry, timestamp, payload, &bytes[offset + size..]))
+}
+
+/// Returns the number of bytes needed to store `value`, at least one.
+fn int_len(value: u64) -> usize {
+    (((64 - value.leading_zeros()) as usize + 7) / 8).max(1)
+}
+
+/// Writes a single record.
+///
+/// The header byte gives the length of each of the following fields minus
+/// one: bits 0-1 for the entry id, bits 2-3 for the payload size and bits
+/// 4-6 for the timestamp. The fields themselves are little-endian.
+fn write_record<W: Write>(
+    out: &mut W,
+    entry: u32,
+    timestamp: u64,
+    payload: &[u8],
+) -> io::Result<()> {
+    let entry_len = int_len(entry as u64);
+    let size_len = int_len(payload.len() as u64);
+    let timestamp_len = int_len(timestamp);
+
+    let header = (entry_len - 1) | ((size_len - 1) << 2) | ((timestamp_len - 1) << 4);
+    out.write_all(&[header as u8])?;
+    out.write_all(&(entry as u64).to_le_bytes()[..entry_len])?;
+    out.write_all(&(payload.len() as u64).to_le_bytes()[..size_len])?;
+    out.write_all(&timestamp.to_le_bytes()[..timestamp_len])?;
+    out.write_all(payload)
+}
+
+/// Returns the DataLog type of an NT4 type string. They only differ for
+/// integers.
+pub fn log_type(nt_type: &str) -> &str {
+    match nt_type {
+        "int" => "int64",
+        "int[]" => "int64[]",
+        other => other,
+    }
+}
+
+/// Returns the NT4 type of a DataLog type, the reverse of [`log_type`].
+pub fn nt_type(log_type: &str) -> &str {
+    match log_type {
+        "int64" => "int",
+        "int64[]" => "int[]",
+        other => other,
+    }
+}
+
+/// Encodes `value` as the payload of a record of the DataLog type
+/// `type_name`.
+fn encode_value(type_name: &str, value: &Value) -> Option<Vec<u8>> {
+    let payload = match type_name {
+        "boolean" => vec![value.as_bool()? as u8],
+        "int64" => value.as_i64()?.to_le_bytes().to_vec(),
+        "float" => (value.as_f64()? as f32).to_le_bytes().to_vec(),
+        "double" => value.as_f64()?.to_le_bytes().to_vec(),
+        "string" | "json" => value.as_str()?.as_bytes().to_vec(),
+        "string[]" => {
+            let items = value.as_array()?;
+            let mut payload = (items.len() as u32).to_le_bytes().to_vec();
+            for item in items {
+                let item = item.as_str()?;
+                payload.extend_from_slice(&(item.len() as u32).to_le_bytes());
+                payload.extend_from_slice(item.as_bytes());
+            }
+            payload
+        }
+        struct_type if struct_type.starts_with("struct:") || struct_type.starts_with("proto:") => {
+            value.as_slice()?.to_vec()
+        }
+        array if array.ends_with("[]") => {
+            let element = array.trim_end_matches("[]");
+            let mut payload = Vec::new();
+            for item in value.as_array()? {
+                payload.extend(encode_value(element, item)?);
+            }
+            payload
+        }
+        // raw and anything else that is sent as bytes
+        _ => value.as_slice()?.to_vec(),
+    };
+
+    Some(payload)
+}
+
+/// Decodes the payload of a record of the DataLog type `type_name`, the
+/// reverse of [`encode_value`].
+fn decode_value(type_name: &str, payload: &[u8]) -> Option<Value> {
+    let value = match type_name {
+        "boolean" => Value::Boolean(*payload.first()? != 0),
+        "int64" => Value::from(i64::from_le_bytes(payload.try_into().ok()?)),
+        "float" => Value::F32(f32::from_le_bytes(payload.try_into().ok()?)),
+        "double" => Value::F64(f64::from_le_bytes(payload.try_into().ok()?)),
+        "string" | "json" => Value::from(String::from_utf8_lossy(payload).into_owned()),
+        "string[]" => {
+            let count = read_int(payload.get(..4)?) as usize;
+            let mut rest = payload.get(4..)?;
+            let mut items = Vec::with_capacity(count.min(rest.len() / 4));
+            for _ in 0..count {
+                let (item, next) = read_string(rest)?;
+                items.push(Value::from(item));
+                rest = next;
+            }
+            Value::Array(items)
+        }
+        struct_type if struct_type.starts_with("struct:") || struct_type.starts_with("proto:") => {
+            Value::Binary(payload.to_vec())
+        }
+        array if array.ends_with("[]") => {
+            let element = array.trim_end_matches("[]");
+            let size = match element {
+                "boolean" => 1,
+                "float" => 4,
+                "int64" | "double" => 8,
+                _ => return None,
+            };
+            Value::Array(
+                payload
+                    .chunks_exact(size)
+                    .map(|item| decode_value(element, item))
+                    .collect::<Option<_>>()?,
+            )
+        }
+        // raw and anything else that is sent as bytes
+        _ => Value::Binary(payload.to_vec()),
+    };
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Control record type of metadata updates, which the writer never
+    /// writes but roboRIO logs contain.
+    const CONTROL_SET_METADATA: u8 = 2;
+
+    #[test]
+    fn reads_back_what_was_written() {
+        // past 32 bits, as after the recorder unwrapped a wrapping NT clock
+        let late = (1 << 32) + 25;
+
+        let mut bytes = Vec::new();
+        let mut writer = WpilogWriter::new(&mut bytes, "Jankboard").unwrap();
+        let speeds = Value::Array(vec![1.into(), (-2).into()]);
+        writer
+            .append("NT:/voltage", "double", &Value::F64(12.5), 10)
+            .unwrap();
+        writer
+            .append("NT:/gear", "string", &Value::from("drive"), 20)
+            .unwrap();
+        writer
+            .append("NT:/ebrake", "boolean", &Value::Boolean(true), 30)
+            .unwrap();
+        writer.append("NT:/speeds", "int64[]", &speeds, 40).unwrap();
+
+        let mut metadata = vec![CONTROL_SET_METADATA];
+        metadata.extend_from_slice(&1u32.to_le_bytes());
+        metadata.extend_from_slice(&2u32.to_le_bytes());
+        metadata.extend_from_slice(b"{}");
+        write_record(&mut writer.out, CONTROL_ENTRY, 35, &metadata).unwrap();
+
+        writer
+            .append("NT:/voltage", "double", &Value::F64(7.25), late)
+            .unwrap();
+        writer.finish(late).unwrap();
+
+        let log = read_wpilog(&bytes).unwrap();
+        let entry = |name: &str, type_name: &str| LogEntry {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+        };
+        let record = |entry, timestamp, value| LogRecord {
+            entry,
+            timestamp,
+            value,
+        };
+
+        assert_eq!(
+            log.entries,
+            [
+                entry("NT:/voltage", "double"),
+                entry("NT:/gear", "string"),
+                entry("NT:/ebrake", "boolean"),
+                entry("NT:/speeds", "int64[]"),
+            ]
+        );
+        assert_eq!(
+            log.records,
+            [
+                record(0, 10, Value::F64(12.5)),
+                record(1, 20, Value::from("drive")),
+                record(2, 30, Value::Boolean(true)),
+                record(3, 40, speeds),
+                record(0, late, Value::F64(7.25)),
+            ]
+        );
+    }
+
+    #[test]
+    fn starts_a_new_entry_when_an_id_is_reused_after_finishing() {
+        let mut bytes = Vec::new();
+        let mut writer = WpilogWriter::new(&mut bytes, "").unwrap();
+        writer
+            .append("NT:/voltage", "double", &Value::F64(12.5), 10)
+            .unwrap();
+        writer.finish(20).unwrap();
+
+        // a second writer reuses entry 1 for a different entry, as the
+        // roboRIO may once an entry is finished
+        let mut writer = WpilogWriter::new(Vec::new(), "").unwrap();
+        writer
+            .append("NT:/gear", "string", &Value::from("park"), 30)
+            .unwrap();
+        let skip_header = 12;
+        bytes.extend_from_slice(&writer.out[skip_header..]);
+
+        // a record cut short by a power loss ends the log
+        bytes.extend_from_slice(&[0x00, 0x01]);
+
+        let log = read_wpilog(&bytes).unwrap();
+        assert_eq!(log.entries.len(), 2);
+        assert_eq!(log.entries[1].name, "NT:/gear");
+        assert_eq!(
+            log.records.iter().map(|r| r.entry).collect::<Vec<_>>(),
+            [0, 1]
+        );
+        assert!(read_wpilog(b"WPILOG").is_err());
+    }
+}
@@ -46,6 +46,7 @@ type Polar = {
  * @property voltage - The voltage of the vehicle's battery.
  * @property acc-profile - The acceleration profile of the vehicle.
  * @property gear - The current gear of the vehicle.
+ * @property speed-limit - The speed limit for the current gear and acceleration profile.
  */
 interface TelemetryData {
   'orientation': number
@@ -62,9 +63,183 @@ interface TelemetryData {
   'ebrake': boolean
   'reorient': boolean
   'gpws': boolean
+  'speed-limit': number
   'connected': boolean
 }
 
+/*
+ * The snapshot of telemetry values sent by the backend in the
+ * `telemetry_state` event, at most once per UI frame.
+ */
+type TelemetryState = Omit<TelemetryData, 'connected'>
+
+/*
+ * The last value received on a topic, as returned by the
+ * `get_telemetry_snapshot` command. `timestamp` is in milliseconds since the
+ * Unix epoch.
+ */
+interface CachedValue {
+  topic: string
+  value: unknown
+  timestamp: number
+  age_ms: number
+  stale: boolean
+}
+
+/*
+ * The state of the link to the robot, as sent in the `telemetry_status`
+ * event. `retry_in` is in milliseconds.
+ */
+type ConnectionState =
+  | { state: 'idle' }
+  | { state: 'resolving' }
+  | { state: 'connecting' }
+  | { state: 'subscribing' }
+  | { state: 'live'; address: string }
+  | { state: 'replaying'; path: string }
+  | { state: 'stale' }
+  | {
+      state: 'backoff'
+      attempt: number
+      max_attempts: number | null
+      retry_in: number
+    }
+  | { state: 'failed'; reason: string }
+
+/*
+ * Sent in the `battery_state` event when the battery level changes or the
+ * voltage dips into the brownout range. Voltages are in volts, and `sag` is
+ * only known while the robot is under load.
+ */
+interface BatteryState {
+  change: 'level' | 'brownout'
+  level: 'good' | 'low' | 'critical' | null
+  voltage: number
+  resting_voltage: number | null
+  sag: number | null
+  under_load: boolean
+  brownouts: number
+}
+
+/*
+ * A collision or rapid deceleration, as sent in the `impact` event and
+ * returned by `get_impact_log`. `magnitude` is in g, `jerk` in g/s, `speed`
+ * in m/s and `direction` in degrees from the robot's x axis.
+ */
+interface Impact {
+  kind: 'collision' | 'rapid_deceleration'
+  magnitude: number
+  jerk: number
+  direction: number
+  speed: number
+  timestamp: number
+}
+
+/*
+ * Sent in the `collision_imminent` event when the robot closes in on an
+ * obstacle fast enough to reach it soon. `distance` is in m and
+ * `closing_speed` in m/s.
+ */
+interface CollisionWarning {
+  distance: number
+  closing_speed: number
+  time_to_collision_ms: number
+  timestamp: number
+}
+
+/*
+ * Sent in the `speed_warning` event when the speed moves closer to or further
+ * from the speed limit. `from` is the zone the speed left.
+ */
+interface SpeedWarning {
+  zone: 'under' | 'approaching' | 'over'
+  from: 'under' | 'approaching' | 'over'
+  speed: number
+  limit: number
+}
+
+/*
+ * A dip of the voltage into the brownout range, sent in the `power_event`
+ * event once the voltage recovers. `timestamp` is when the dip started, in
+ * milliseconds since the Unix epoch.
+ */
+interface PowerEvent {
+  timestamp: number
+  recovery_ms: number
+  min_voltage: number
+  gear: Gear
+  acc_profile: Mode
+  speed: number
+}
+
+/*
+ * The power events of the session, as returned by `get_power_stats`.
+ */
+interface PowerStats {
+  count: number
+  min_voltage: number | null
+  total_recovery_ms: number
+  longest_recovery_ms: number
+  mean_recovery_ms: number | null
+  events: PowerEvent[]
+}
+
+/*
+ * A transition of the robot's gear or acceleration profile, sent in the
+ * `drive_state_changed` event. `timestamp` is in milliseconds since the Unix
+ * epoch.
+ */
+type DriveStateChange =
+  | { kind: 'gear'; from: Gear; to: Gear; timestamp: number }
+  | { kind: 'acc_profile'; from: Mode; to: Mode; timestamp: number }
+
+type MatchPhase =
+  | 'disabled'
+  | 'autonomous'
+  | 'teleop'
+  | 'test'
+  | 'emergency_stopped'
+
+/*
+ * Sent in the `match_phase_changed` event. `match_elapsed_ms` is null until a
+ * match has started.
+ */
+interface MatchPhaseChange {
+  from: MatchPhase
+  to: MatchPhase
+  match_elapsed_ms: number | null
+}
+
+/*
+ * The running match clock, as returned by `get_match_clock`.
+ */
+interface MatchClock {
+  phase: MatchPhase
+  phase_elapsed_ms: number
+  match_elapsed_ms: number | null
+}
+
+/*
+ * Sent in the `recording_state` event when a session recording starts or
+ * stops. `path` is the `.wpilog` file being written.
+ */
+interface RecordingState {
+  recording: boolean
+  path: string
+}
+
+/*
+ * Sent in the `replay_status` event while a recorded session is replayed.
+ * Positions are in milliseconds from the start of the recording.
+ */
+interface ReplayStatus {
+  path: string
+  playing: boolean
+  speed: number
+  position_ms: number
+  duration_ms: number
+}
+
 type CardinalDirection =
   | 'North'
   | 'Northeast'
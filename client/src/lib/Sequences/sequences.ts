@@ -20,7 +20,6 @@ import { settingsStore } from '../stores/settingsStore'
 import { get } from 'svelte/store'
 import getVoicePath from '../utils/getVoicePath'
 import { tick } from 'svelte'
-import { cameraState } from '../Dashboard/Visualization/CameraControls/utils/cameraStore'
 
 // await a "tick" (a svelte update frame) at the start of every sequence so that
 // state is synced and no weird side effects occur
@@ -109,6 +108,23 @@ const periodicSequence = async () => {
   counter++
   setTimeout(periodicSequence, 1000)
 }
+
+export const autonomousPeriodStartedSequence = async () => {
+  await tick()
+  Notifications.info('Autonomous period started', {
+    withAudio: true,
+    src: getVoicePath('autonomous-period-started'),
+  })
+}
+
+export const teleoperatedPeriodStartedSequence = async () => {
+  await tick()
+  Notifications.info('Teleoperated period started', {
+    withAudio: true,
+    src: getVoicePath('teleoperated-period-started'),
+  })
+}
+
 export const criticalFailureIminentSequence = async () => {
   await tick()
   Notifications.error('Critical robot failure imminent', {
@@ -117,6 +133,38 @@ export const criticalFailureIminentSequence = async () => {
   })
 }
 
+export const batteryGoodSequence = async () => {
+  await tick()
+  Notifications.success('Battery good', {
+    withAudio: true,
+    src: getVoicePath('battery-good'),
+  })
+}
+
+export const batteryLowSequence = async () => {
+  await tick()
+  Notifications.warn('Battery low', {
+    withAudio: true,
+    src: getVoicePath('battery-low'),
+  })
+}
+
+export const batteryCriticallyLowSequence = async () => {
+  await tick()
+  Notifications.error('Battery critically low', {
+    withAudio: true,
+    src: getVoicePath('battery-critically-low'),
+  })
+}
+
+export const batteryFaultsDetectedSequence = async () => {
+  await tick()
+  Notifications.error('Battery faults detected', {
+    withAudio: true,
+    src: getVoicePath('battery-faults-detected'),
+  })
+}
+
 export const collisionDetectedSequence = async () => {
   await tick()
   Notifications.error('Collision detected', {
@@ -133,6 +181,30 @@ export const collisionImminentSequence = async () => {
   })
 }
 
+export const rapidDecelerationDetectedSequence = async () => {
+  await tick()
+  Notifications.warn('Rapid deceleration detected', {
+    withAudio: true,
+    src: getVoicePath('rapid-deceleration-detected'),
+  })
+}
+
+export const rapidlyApproachingSpeedSequence = async () => {
+  await tick()
+  Notifications.warn('Rapidly approaching speed limit', {
+    withAudio: true,
+    src: getVoicePath('rapidly-approaching-speed'),
+  })
+}
+
+export const overspeedSequence = async () => {
+  await tick()
+  Notifications.error('Overspeed', {
+    withAudio: true,
+    src: getVoicePath('overspeed'),
+  })
+}
+
 export const cruiseControlEngagedSequence = async () => {
   if (get(settingsStore).disableAnnoyances) return
   await tick()
@@ -318,7 +390,6 @@ const bypassCoprocessorRestrictionsSequence = async () => {
 
 export const shiftedInParkSequence = async () => {
   await tick()
-  cameraState.set('mode', 'orbit')
 
   if (
     get(settingsStore).disableAnnoyances ||
@@ -336,8 +407,6 @@ export const shiftedInParkSequence = async () => {
 export const shiftedInReverseSequence = async () => {
   await tick()
 
-  cameraState.set('mode', 'follow-direction')
-
   if (
     get(settingsStore).disableAnnoyances ||
     !get(sequenceStore).initializationComplete
@@ -349,8 +418,6 @@ export const shiftedInReverseSequence = async () => {
 export const shiftedInNeutralSequence = async () => {
   await tick()
 
-  cameraState.set('mode', 'orbit')
-
   if (
     get(settingsStore).disableAnnoyances ||
     !get(sequenceStore).initializationComplete
@@ -362,8 +429,6 @@ export const shiftedInNeutralSequence = async () => {
 export const shiftedInLowSequence = async () => {
   await tick()
 
-  cameraState.set('mode', 'follow-facing')
-
   if (
     get(settingsStore).disableAnnoyances ||
     !get(sequenceStore).initializationComplete
@@ -375,8 +440,6 @@ export const shiftedInLowSequence = async () => {
 export const shiftedInAutoSequence = async () => {
   await tick()
 
-  cameraState.set('mode', 'follow-direction')
-
   if (
     get(settingsStore).disableAnnoyances ||
     !get(sequenceStore).initializationComplete
@@ -388,8 +451,6 @@ export const shiftedInAutoSequence = async () => {
 export const shiftedInDriveSequence = async () => {
   await tick()
 
-  cameraState.set('mode', 'follow-facing')
-
   if (
     get(settingsStore).disableAnnoyances ||
     !get(sequenceStore).initializationComplete
@@ -0,0 +1,5 @@
+import { writable, readonly } from 'svelte/store'
+
+export const connectionStore = writable<ConnectionState>({ state: 'idle' })
+
+export const connectionReadonlyStore = readonly(connectionStore)
@@ -15,6 +15,7 @@ let defaults: TelemetryData = {
   'ebrake': false,
   'reorient': false,
   'gpws': false,
+  'speed-limit': 5,
   'connected': false,
 }
 
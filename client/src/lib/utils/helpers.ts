@@ -31,6 +31,7 @@ let defaults: TelemetryData = {
   'ebrake': false,
   'reorient': false,
   'gpws': false,
+  'speed-limit': 5,
 }
 
 /**
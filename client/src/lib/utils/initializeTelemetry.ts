@@ -1,6 +1,31 @@
-import { gpwsTriggeredSequence } from '../Sequences/sequences'
+import {
+  autonomousPeriodStartedSequence,
+  batteryCriticallyLowSequence,
+  batteryFaultsDetectedSequence,
+  batteryGoodSequence,
+  batteryLowSequence,
+  collisionDetectedSequence,
+  collisionImminentSequence,
+  gpwsTriggeredSequence,
+  modeChillSequence,
+  modeCruiseSequence,
+  modeLudicrousSequence,
+  overspeedSequence,
+  rapidDecelerationDetectedSequence,
+  rapidlyApproachingSpeedSequence,
+  shiftedInAutoSequence,
+  shiftedInDriveSequence,
+  shiftedInLowSequence,
+  shiftedInNeutralSequence,
+  shiftedInParkSequence,
+  shiftedInReverseSequence,
+  teleoperatedPeriodStartedSequence,
+} from '../Sequences/sequences'
 import { telemetryStore } from '../stores/telemetryStore'
+import { connectionStore } from '../stores/connectionStore'
 import { listen } from '@tauri-apps/api/event'
+import { invoke } from '@tauri-apps/api/tauri'
+import { get } from 'svelte/store'
 
 /**
  * Connects to sockets and subscribes to specified topics to receive telemetry data.
@@ -11,30 +36,161 @@ import { listen } from '@tauri-apps/api/event'
  */
 
 export const initializeTelemetry = async () => {
-  const unlistenStatus = await listen('telemetry_status', (event) => {
-    if (event.payload === 'connected') {
-      telemetryStore.set('connected', true)
-    } else if (event.payload === 'disconnected') {
-      telemetryStore.reset()
+  const unlistenStatus = await listen<ConnectionState>(
+    'telemetry_status',
+    (event) => {
+      connectionStore.set(event.payload)
+      if (
+        event.payload.state === 'live' ||
+        event.payload.state === 'replaying'
+      ) {
+        telemetryStore.set('connected', true)
+      } else if (event.payload.state !== 'stale') {
+        telemetryStore.reset()
+      }
     }
-  })
+  )
 
-  const unlistenTelemetry = await listen('telemetry_data', (event) => {
-    const data = JSON.parse(event.payload as string)
-    telemetryStore.set(data['topic_name'], data['data'])
-  })
+  const unlistenTelemetry = await listen<TelemetryState>(
+    'telemetry_state',
+    (event) => {
+      telemetryStore.update({ ...get(telemetryStore), ...event.payload })
+    }
+  )
 
-  const unlistenGPWS = await listen('telemetry_gpws', (event) => {
-    const data = JSON.parse(event.payload as string) as boolean
-    if (data) {
+  const unlistenGPWS = await listen<boolean>('telemetry_gpws', (event) => {
+    if (event.payload) {
       gpwsTriggeredSequence()
     }
   })
 
+  const unlistenBattery = await listen<BatteryState>(
+    'battery_state',
+    (event) => {
+      const battery = event.payload
+      if (battery.change === 'brownout') {
+        batteryFaultsDetectedSequence()
+      } else if (battery.level === 'critical') {
+        batteryCriticallyLowSequence()
+      } else if (battery.level === 'low') {
+        batteryLowSequence()
+      } else if (battery.level === 'good') {
+        batteryGoodSequence()
+      }
+    }
+  )
+
+  const unlistenImpact = await listen<Impact>('impact', (event) => {
+    if (event.payload.kind === 'collision') {
+      collisionDetectedSequence()
+    } else {
+      rapidDecelerationDetectedSequence()
+    }
+  })
+
+  const unlistenImminent = await listen<CollisionWarning>(
+    'collision_imminent',
+    () => {
+      collisionImminentSequence()
+    }
+  )
+
+  const unlistenSpeed = await listen<SpeedWarning>(
+    'speed_warning',
+    (event) => {
+      if (event.payload.zone === 'over') {
+        overspeedSequence()
+      } else if (
+        event.payload.zone === 'approaching' &&
+        event.payload.from === 'under'
+      ) {
+        rapidlyApproachingSpeedSequence()
+      }
+    }
+  )
+
+  const unlistenDriveState = await listen<DriveStateChange>(
+    'drive_state_changed',
+    (event) => {
+      const change = event.payload
+      if (change.kind === 'gear') {
+        switch (change.to) {
+          case 'park':
+            shiftedInParkSequence()
+            break
+          case 'reverse':
+            shiftedInReverseSequence()
+            break
+          case 'neutral':
+            shiftedInNeutralSequence()
+            break
+          case 'low':
+            shiftedInLowSequence()
+            break
+          case 'auto':
+            shiftedInAutoSequence()
+            break
+          case 'drive':
+            shiftedInDriveSequence()
+            break
+        }
+      } else {
+        switch (change.to) {
+          case 'chill':
+            modeChillSequence()
+            break
+          case 'cruise':
+            modeCruiseSequence()
+            break
+          case 'ludicrous':
+            modeLudicrousSequence()
+            break
+        }
+      }
+    }
+  )
+
+  const unlistenMatchPhase = await listen<MatchPhaseChange>(
+    'match_phase_changed',
+    (event) => {
+      if (event.payload.to === 'autonomous') {
+        autonomousPeriodStartedSequence()
+      } else if (event.payload.to === 'teleop') {
+        teleoperatedPeriodStartedSequence()
+      }
+    }
+  )
+
+  // hydrate with the values received before this window started listening
+  const [status, snapshot] = await Promise.all([
+    invoke<ConnectionState>('get_telemetry_status'),
+    invoke<CachedValue[]>('get_telemetry_snapshot'),
+  ])
+  connectionStore.set(status)
+  if (
+    status.state === 'live' ||
+    status.state === 'replaying' ||
+    status.state === 'stale'
+  ) {
+    const hydrated: Partial<TelemetryData> = { connected: true }
+    for (const { topic, value } of snapshot) {
+      if (topic in get(telemetryStore)) {
+        hydrated[topic as keyof TelemetryData] = value as never
+      }
+    }
+    telemetryStore.update({ ...get(telemetryStore), ...hydrated })
+  }
+
   const unlistenAll = () => {
     unlistenStatus()
     unlistenTelemetry()
     unlistenGPWS()
+    unlistenBattery()
+    unlistenImpact()
+    unlistenImminent()
+    unlistenSpeed()
+    unlistenDriveState()
+    unlistenMatchPhase()
   }
 
   return unlistenAll